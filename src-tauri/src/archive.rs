@@ -0,0 +1,720 @@
+use flate2::read::GzDecoder;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+use xz2::read::XzDecoder;
+use zip::ZipArchive;
+
+use crate::jobs::JobProgress;
+use crate::migoto;
+
+// Clutter added by archivers and file browsers, never part of a mod
+const JUNK_NAMES: &[&str] = &["__MACOSX", ".DS_Store", "Thumbs.db", "desktop.ini"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    Zip,
+    SevenZip,
+    Rar,
+    Tar,
+    TarGz,
+    TarXz,
+}
+
+/// Identifies an archive by its first bytes rather than its extension, which
+/// mod sites frequently get wrong.
+pub fn detect_format(path: &Path) -> Result<ArchiveFormat, String> {
+    let mut header = [0u8; 262];
+    let mut file = fs::File::open(path)
+        .map_err(|e| format!("Failed to open archive: {}", e))?;
+    let read = read_up_to(&mut file, &mut header)
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+    let header = &header[..read];
+
+    let format = if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+        ArchiveFormat::Zip
+    } else if header.starts_with(&[0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C]) {
+        ArchiveFormat::SevenZip
+    } else if header.starts_with(b"Rar!\x1a\x07") {
+        ArchiveFormat::Rar
+    } else if header.starts_with(&[0x1F, 0x8B]) {
+        compressed_tar(&mut file, ArchiveFormat::TarGz)?
+    } else if header.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]) {
+        compressed_tar(&mut file, ArchiveFormat::TarXz)?
+    } else if is_tar_header(header) {
+        ArchiveFormat::Tar
+    } else {
+        return Err("Unsupported archive format (expected zip, 7z, rar or tar)".to_string());
+    };
+
+    Ok(format)
+}
+
+fn is_tar_header(header: &[u8]) -> bool {
+    header.len() >= 262 && &header[257..262] == b"ustar"
+}
+
+// Gzip and xz compress a single file, so they only hold mods when that file
+// is a tar archive.
+fn compressed_tar(file: &mut fs::File, format: ArchiveFormat) -> Result<ArchiveFormat, String> {
+    file.rewind()
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+    let mut header = [0u8; 262];
+    let read = match format {
+        ArchiveFormat::TarGz => read_up_to(&mut GzDecoder::new(file), &mut header),
+        _ => read_up_to(&mut XzDecoder::new(file), &mut header),
+    }
+    .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+    if !is_tar_header(&header[..read]) {
+        return Err("Unsupported archive format: a compressed file that is not a tar archive".to_string());
+    }
+    Ok(format)
+}
+
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+// Turns an entry name into a relative path, refusing anything that would
+// land outside the destination folder.
+fn enclosed_path(name: &str) -> Option<PathBuf> {
+    let normalized = name.replace('\\', "/");
+    let mut path = PathBuf::new();
+
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+fn is_junk(relative: &Path) -> bool {
+    relative.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        name.starts_with("._") || JUNK_NAMES.iter().any(|junk| name.eq_ignore_ascii_case(junk))
+    })
+}
+
+// The path an entry is extracted to, or None for unsafe and junk entries.
+fn entry_path(name: &str) -> Option<PathBuf> {
+    enclosed_path(name).filter(|path| !is_junk(path))
+}
+
+// Writes one entry, returning the number of bytes written.
+fn write_entry(dest: &Path, relative: &Path, is_dir: bool, reader: &mut dyn Read) -> Result<u64, String> {
+    let outpath = dest.join(relative);
+
+    if is_dir {
+        fs::create_dir_all(&outpath)
+            .map_err(|e| format!("Failed to create directory: {}", e))?;
+        return Ok(0);
+    }
+
+    if let Some(parent) = outpath.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create parent directory: {}", e))?;
+    }
+    let mut outfile = fs::File::create(&outpath)
+        .map_err(|e| format!("Failed to create file: {}", e))?;
+    io::copy(reader, &mut outfile)
+        .map_err(|e| format!("Failed to extract file: {}", e))
+}
+
+type Progress<'a> = &'a mut dyn FnMut(&JobProgress) -> Result<(), String>;
+
+// Counts one more entry and reports it. An error from `progress` stops the
+// extraction.
+fn advance(state: &mut JobProgress, relative: Option<&Path>, bytes: u64, progress: Progress) -> Result<(), String> {
+    state.files_done += 1;
+    state.bytes_done += bytes;
+    state.current_path = relative.map(|path| path.to_string_lossy().to_string()).unwrap_or_default();
+    progress(state)
+}
+
+/// Extracts `archive` into `dest`, calling `progress` after each entry. An
+/// error returned by `progress`, such as a cancellation, stops the extraction.
+pub fn extract(archive: &Path, dest: &Path, mut progress: impl FnMut(&JobProgress) -> Result<(), String>) -> Result<(), String> {
+    match detect_format(archive)? {
+        ArchiveFormat::Zip => extract_zip(archive, dest, &mut progress),
+        ArchiveFormat::SevenZip => extract_7z(archive, dest, &mut progress),
+        ArchiveFormat::Rar => extract_rar(archive, dest, &mut progress),
+        format => extract_tar(archive, format, dest, &mut progress),
+    }
+}
+
+fn extract_zip(archive: &Path, dest: &Path, progress: Progress) -> Result<(), String> {
+    let file = fs::File::open(archive)
+        .map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut zip = ZipArchive::new(file)
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+    let mut state = JobProgress { files_total: zip.len(), ..Default::default() };
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)
+            .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+
+        let relative = entry.enclosed_name().filter(|path| !is_junk(path)).map(Path::to_path_buf);
+        let mut written = 0;
+        if let Some(relative) = &relative {
+            let is_dir = entry.is_dir();
+            written = write_entry(dest, relative, is_dir, &mut entry)?;
+        }
+        advance(&mut state, relative.as_deref(), written, progress)?;
+    }
+
+    Ok(())
+}
+
+fn extract_7z(archive: &Path, dest: &Path, progress: Progress) -> Result<(), String> {
+    let mut reader = sevenz_rust::SevenZReader::open(archive, sevenz_rust::Password::empty())
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+    let mut state = JobProgress { files_total: reader.archive().files.len(), ..Default::default() };
+    let mut failure = None;
+
+    reader.for_each_entries(|entry, data| {
+        let relative = entry_path(entry.name());
+        // Entries of a solid block share one stream, so a skipped entry
+        // still has to be read past for the next one to start in the right place
+        let written = match &relative {
+            Some(relative) => write_entry(dest, relative, entry.is_directory(), data),
+            None => io::copy(data, &mut io::sink())
+                .map(|_| 0)
+                .map_err(|e| format!("Failed to read archive entry: {}", e)),
+        };
+
+        match written.and_then(|written| advance(&mut state, relative.as_deref(), written, progress)) {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                failure = Some(e);
+                Ok(false)
+            }
+        }
+    }).map_err(|e| format!("Failed to read archive entry: {}", e))?;
+
+    failure.map_or(Ok(()), Err)
+}
+
+fn extract_rar(archive: &Path, dest: &Path, progress: Progress) -> Result<(), String> {
+    let total = unrar::Archive::new(archive)
+        .open_for_listing()
+        .map_err(|e| format!("Failed to read archive: {}", e))?
+        .count();
+
+    let mut cursor = unrar::Archive::new(archive)
+        .open_for_processing()
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+    let mut state = JobProgress { files_total: total, ..Default::default() };
+
+    while let Some(header) = cursor.read_header()
+        .map_err(|e| format!("Failed to read archive entry: {}", e))?
+    {
+        let entry = header.entry();
+        let relative = entry_path(&entry.filename.to_string_lossy());
+
+        let mut outfile = None;
+        cursor = match &relative {
+            Some(relative) if entry.is_directory() => {
+                fs::create_dir_all(dest.join(relative))
+                    .map_err(|e| format!("Failed to create directory: {}", e))?;
+                header.skip()
+            }
+            Some(relative) => {
+                let outpath = dest.join(relative);
+                if let Some(parent) = outpath.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create parent directory: {}", e))?;
+                }
+                outfile = Some(outpath.clone());
+                header.extract_to(&outpath)
+            }
+            None => header.skip(),
+        }.map_err(|e| format!("Failed to extract file: {}", e))?;
+
+        let written = outfile.and_then(|path| fs::metadata(path).ok()).map_or(0, |m| m.len());
+        advance(&mut state, relative.as_deref(), written, progress)?;
+    }
+
+    Ok(())
+}
+
+fn open_tar(archive: &Path, format: ArchiveFormat) -> Result<tar::Archive<Box<dyn Read>>, String> {
+    let file = fs::File::open(archive)
+        .map_err(|e| format!("Failed to open archive: {}", e))?;
+
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::TarGz => Box::new(GzDecoder::new(file)),
+        ArchiveFormat::TarXz => Box::new(XzDecoder::new(file)),
+        _ => Box::new(file),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+fn extract_tar(archive: &Path, format: ArchiveFormat, dest: &Path, progress: Progress) -> Result<(), String> {
+    // Compressed tar streams can't be counted without reading them through once
+    let total = open_tar(archive, format)?
+        .entries()
+        .map_err(|e| format!("Failed to read archive: {}", e))?
+        .count();
+    let mut state = JobProgress { files_total: total, ..Default::default() };
+
+    let mut tar = open_tar(archive, format)?;
+    let entries = tar.entries()
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+    for entry in entries {
+        let mut entry = entry
+            .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+        let kind = entry.header().entry_type();
+
+        // Links and device files have no place in a mod folder
+        let mut relative = None;
+        let mut written = 0;
+        if kind.is_file() || kind.is_dir() {
+            let name = entry.path()
+                .map_err(|e| format!("Failed to read archive entry: {}", e))?
+                .to_string_lossy()
+                .to_string();
+
+            relative = entry_path(&name);
+            if let Some(relative) = &relative {
+                written = write_entry(dest, relative, kind.is_dir(), &mut entry)?;
+            }
+        }
+        advance(&mut state, relative.as_deref(), written, progress)?;
+    }
+
+    Ok(())
+}
+
+/// Every file in `archive` that extraction would write, as a relative path.
+pub fn list_files(archive: &Path) -> Result<Vec<PathBuf>, String> {
+    let format = detect_format(archive)?;
+    let mut files = Vec::new();
+
+    match format {
+        ArchiveFormat::Zip => {
+            let file = fs::File::open(archive)
+                .map_err(|e| format!("Failed to open archive: {}", e))?;
+            let mut zip = ZipArchive::new(file)
+                .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+            for i in 0..zip.len() {
+                let entry = zip.by_index_raw(i)
+                    .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+                if !entry.is_dir() {
+                    files.extend(entry.enclosed_name().filter(|path| !is_junk(path)).map(Path::to_path_buf));
+                }
+            }
+        }
+        ArchiveFormat::SevenZip => {
+            let reader = sevenz_rust::SevenZReader::open(archive, sevenz_rust::Password::empty())
+                .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+            files.extend(reader.archive().files.iter()
+                .filter(|entry| !entry.is_directory())
+                .filter_map(|entry| entry_path(entry.name())));
+        }
+        ArchiveFormat::Rar => {
+            let listing = unrar::Archive::new(archive)
+                .open_for_listing()
+                .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+            for header in listing {
+                let header = header
+                    .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+                if !header.is_directory() {
+                    files.extend(entry_path(&header.filename.to_string_lossy()));
+                }
+            }
+        }
+        format => {
+            let mut tar = open_tar(archive, format)?;
+            let entries = tar.entries()
+                .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+            for entry in entries {
+                let entry = entry
+                    .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+                if entry.header().entry_type().is_file() {
+                    let name = entry.path()
+                        .map_err(|e| format!("Failed to read archive entry: {}", e))?
+                        .to_string_lossy()
+                        .to_string();
+                    files.extend(entry_path(&name));
+                }
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn collect_files(dir: &Path, base: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, base, files)?;
+        } else if let Ok(relative) = path.strip_prefix(base) {
+            files.push(relative.to_path_buf());
+        }
+    }
+
+    Ok(())
+}
+
+// Leading folders every file shares, as long as no file sits beside them, so
+// `ModName/ModName/mod.ini` resolves to `ModName/ModName`.
+fn strip_wrappers(files: &[&Path]) -> PathBuf {
+    let mut prefix = PathBuf::new();
+
+    loop {
+        let mut shared = None;
+        for file in files {
+            let mut parts = file.strip_prefix(&prefix).unwrap_or(file).components();
+            let first = parts.next();
+            if parts.next().is_none() {
+                return prefix;
+            }
+
+            match shared {
+                None => shared = first,
+                Some(folder) if Some(folder) == first => {}
+                Some(_) => return prefix,
+            }
+        }
+
+        match shared {
+            Some(folder) => prefix.push(folder),
+            None => return prefix,
+        }
+    }
+}
+
+/// A folder of an archive that holds a mod of its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModRoot {
+    // Folder name inside the archive, empty for an archive holding one mod
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Works out which folders of an archive, given its files, should each become
+/// a mod. A single mod comes back as one root, however deeply it was wrapped.
+/// An archive with no ini of its own at the top but several folders that
+/// have one, such as a bundle of mods or of variants of one mod, comes back
+/// as one root per folder.
+pub fn mod_roots(files: &[PathBuf]) -> Vec<ModRoot> {
+    let all: Vec<&Path> = files.iter().map(PathBuf::as_path).collect();
+    let top = strip_wrappers(&all);
+    let single = vec![ModRoot { name: String::new(), path: top.clone() }];
+
+    let mut folders: BTreeMap<String, Vec<&Path>> = BTreeMap::new();
+    for file in &all {
+        let relative = file.strip_prefix(&top).unwrap_or(file);
+        let mut parts = relative.components();
+        let first = parts.next().map(|c| c.as_os_str().to_string_lossy().to_string());
+
+        match (first, parts.next()) {
+            (_, None) if migoto::is_ini(relative) => return single,
+            (Some(folder), Some(_)) => folders.entry(folder).or_default().push(relative),
+            _ => {}
+        }
+    }
+
+    folders.retain(|_, files| files.iter().any(|file| migoto::is_ini(file)));
+    if folders.len() < 2 {
+        return single;
+    }
+
+    folders.into_iter()
+        .map(|(name, files)| {
+            let path = top.join(strip_wrappers(&files));
+            ModRoot { name, path }
+        })
+        .collect()
+}
+
+/// Folders of an archive that each hold a mod, for the user to pick from
+/// before importing. Empty when the archive holds a single mod.
+#[tauri::command]
+pub async fn inspect_archive(archive_path: String) -> Result<Vec<String>, String> {
+    let roots = mod_roots(&list_files(Path::new(&archive_path))?);
+    if roots.len() < 2 {
+        return Ok(vec![]);
+    }
+    Ok(roots.into_iter().map(|root| root.name).collect())
+}
+
+/// Moves the mods found in `extracted` into `dest_root` and returns their
+/// names. A single mod is named `dest_name` and bundled mods keep their folder
+/// names. When `variants` is given only those folders are installed, named
+/// `<dest_name> - <variant>`.
+pub fn install_mods(extracted: &Path, dest_root: &Path, dest_name: &str, variants: Option<&[String]>) -> Result<Vec<String>, String> {
+    let mut files = Vec::new();
+    collect_files(extracted, extracted, &mut files)?;
+    // An archive of nothing but junk would otherwise install as an empty mod
+    if files.is_empty() {
+        return Err("No mod files found in archive".to_string());
+    }
+    let roots = mod_roots(&files);
+
+    let targets: Vec<(PathBuf, String)> = match variants {
+        Some(variants) => {
+            let mut targets = Vec::new();
+            for variant in variants {
+                let root = roots.iter()
+                    .find(|root| !root.name.is_empty() && &root.name == variant)
+                    .ok_or_else(|| format!("Variant not found in archive: {}", variant))?;
+                targets.push((root.path.clone(), format!("{} - {}", dest_name, variant)));
+            }
+            targets
+        }
+        None if roots.len() == 1 => vec![(roots[0].path.clone(), dest_name.to_string())],
+        None => roots.into_iter().map(|root| (root.path, root.name)).collect(),
+    };
+
+    if targets.is_empty() {
+        return Err("No variants selected".to_string());
+    }
+
+    let taken: Vec<&str> = targets.iter()
+        .filter(|(_, name)| dest_root.join(name).exists())
+        .map(|(_, name)| name.as_str())
+        .collect();
+    if !taken.is_empty() {
+        return Err(format!("Destination already exists: {}", taken.join(", ")));
+    }
+
+    let mut installed: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (root, name) in &targets {
+        let source = extracted.join(root);
+        let target = dest_root.join(name);
+
+        if let Err(e) = fs::rename(&source, &target) {
+            // Put back the mods already moved so the import is all or nothing
+            for (from, to) in installed.iter().rev() {
+                let _ = fs::rename(to, from);
+            }
+            return Err(format!("Failed to move {} into place: {}", name, e));
+        }
+        installed.push((source, target));
+    }
+
+    Ok(targets.into_iter().map(|(_, name)| name).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("wwmm-archive-{}-{}", label, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn paths(files: &[&str]) -> Vec<PathBuf> {
+        files.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn strips_wrapper_folders() {
+        let cases: &[(&[&str], &str)] = &[
+            (&[], ""),
+            (&["mod.ini"], ""),
+            (&["Mod/mod.ini", "Mod/textures/body.dds"], "Mod"),
+            (&["Mod/Mod/mod.ini", "Mod/Mod/textures/body.dds"], "Mod/Mod"),
+            (&["Mod/mod.ini", "Mod/Red/red.ini"], "Mod"),
+            (&["Red/mod.ini", "Blue/mod.ini"], ""),
+            (&["Pack/readme.txt", "Pack/Red/mod.ini"], "Pack"),
+        ];
+
+        for (files, expected) in cases {
+            let files = paths(files);
+            let files: Vec<&Path> = files.iter().map(PathBuf::as_path).collect();
+            assert_eq!(strip_wrappers(&files), Path::new(expected), "{:?}", files);
+        }
+    }
+
+    // Layout, files in the archive, and the expected (name, path) of each root
+    type RootCase<'a> = (&'a str, &'a [&'a str], &'a [(&'a str, &'a str)]);
+
+    #[test]
+    fn finds_mod_roots() {
+        let cases: &[RootCase] = &[
+            ("wrapped mod", &["Wrap/Mod/mod.ini", "Wrap/Mod/textures/body.dds"], &[("", "Wrap/Mod")]),
+            (
+                "variants",
+                &["Pack/readme.txt", "Pack/Red/Red/mod.ini", "Pack/Red/Red/body.dds", "Pack/Blue/mod.ini"],
+                &[("Blue", "Pack/Blue"), ("Red", "Pack/Red/Red")],
+            ),
+            ("ini beside variant folders", &["mod.ini", "Red/red.ini", "Blue/blue.ini"], &[("", "")]),
+            ("one folder with an ini", &["Mod/mod.ini", "Docs/readme.txt"], &[("", "")]),
+            ("no ini at all", &["Red/body.dds", "Blue/body.dds"], &[("", "")]),
+            ("empty", &[], &[("", "")]),
+        ];
+
+        for (layout, files, expected) in cases {
+            let roots: Vec<(String, PathBuf)> = mod_roots(&paths(files)).into_iter()
+                .map(|root| (root.name, root.path))
+                .collect();
+            let expected: Vec<(String, PathBuf)> = expected.iter()
+                .map(|(name, path)| (name.to_string(), PathBuf::from(path)))
+                .collect();
+            assert_eq!(roots, expected, "{}", layout);
+        }
+    }
+
+    // An extracted archive holding the variants Red and Blue of one mod
+    fn variant_pack(dir: &TempDir) -> (PathBuf, PathBuf) {
+        let extracted = dir.0.join("staging");
+        write(&extracted.join("Pack/Red/mod.ini"), "red");
+        write(&extracted.join("Pack/Blue/mod.ini"), "blue");
+        write(&extracted.join("Pack/readme.txt"), "pick one");
+        let dest = dir.0.join("Mods");
+        fs::create_dir_all(&dest).unwrap();
+        (extracted, dest)
+    }
+
+    #[test]
+    fn installs_selected_variants() {
+        let dir = TempDir::new("variants");
+        let (extracted, dest) = variant_pack(&dir);
+
+        let names = install_mods(&extracted, &dest, "Outfit", Some(&["Red".to_string()])).unwrap();
+
+        assert_eq!(names, ["Outfit - Red"]);
+        assert_eq!(fs::read_to_string(dest.join("Outfit - Red/mod.ini")).unwrap(), "red");
+        assert!(!dest.join("Outfit - Blue").exists());
+
+        let missing = install_mods(&extracted, &dest, "Outfit", Some(&["Green".to_string()]));
+        assert_eq!(missing, Err("Variant not found in archive: Green".to_string()));
+    }
+
+    #[test]
+    fn installs_a_bundle_under_its_folder_names() {
+        let dir = TempDir::new("bundle");
+        let (extracted, dest) = variant_pack(&dir);
+
+        let names = install_mods(&extracted, &dest, "Outfit", None).unwrap();
+
+        assert_eq!(names, ["Blue", "Red"]);
+        assert_eq!(fs::read_to_string(dest.join("Blue/mod.ini")).unwrap(), "blue");
+        assert_eq!(fs::read_to_string(dest.join("Red/mod.ini")).unwrap(), "red");
+    }
+
+    #[test]
+    fn installs_a_wrapped_mod_under_the_given_name() {
+        let dir = TempDir::new("single");
+        let extracted = dir.0.join("staging");
+        write(&extracted.join("Mod/Mod/mod.ini"), "[Constants]\n");
+        write(&extracted.join("Mod/Mod/textures/body.dds"), "texture data");
+        let dest = dir.0.join("Mods");
+        fs::create_dir_all(&dest).unwrap();
+
+        assert_eq!(install_mods(&extracted, &dest, "Outfit", None).unwrap(), ["Outfit"]);
+        assert_eq!(fs::read_to_string(dest.join("Outfit/textures/body.dds")).unwrap(), "texture data");
+    }
+
+    #[test]
+    fn refuses_taken_names_before_moving_anything() {
+        let dir = TempDir::new("taken");
+        let (extracted, dest) = variant_pack(&dir);
+        write(&dest.join("Outfit - Blue/mod.ini"), "installed earlier");
+
+        let result = install_mods(&extracted, &dest, "Outfit", Some(&["Red".to_string(), "Blue".to_string()]));
+
+        assert_eq!(result, Err("Destination already exists: Outfit - Blue".to_string()));
+        assert!(!dest.join("Outfit - Red").exists());
+        assert!(extracted.join("Pack/Red/mod.ini").exists());
+        assert_eq!(fs::read_to_string(dest.join("Outfit - Blue/mod.ini")).unwrap(), "installed earlier");
+    }
+
+    #[test]
+    fn refuses_an_archive_of_only_junk() {
+        let dir = TempDir::new("junk");
+        let extracted = dir.0.join("staging");
+        // Extraction leaves only the folders junk entries were skipped from
+        fs::create_dir_all(extracted.join("Mod")).unwrap();
+        let dest = dir.0.join("Mods");
+        fs::create_dir_all(&dest).unwrap();
+
+        let result = install_mods(&extracted, &dest, "Outfit", None);
+
+        assert_eq!(result, Err("No mod files found in archive".to_string()));
+        assert!(!dest.join("Outfit").exists());
+    }
+
+    fn solid_7z(path: &Path, files: &[(&str, &str)]) {
+        use sevenz_rust::{SevenZArchiveEntry, SevenZWriter, SeqReader, SourceReader};
+
+        let entries = files.iter().map(|(name, content)| {
+            let mut entry = SevenZArchiveEntry::new();
+            entry.name = name.to_string();
+            entry.has_stream = true;
+            entry.size = content.len() as u64;
+            entry
+        }).collect();
+        let readers = files.iter().map(|(_, content)| SourceReader::new(content.as_bytes())).collect();
+
+        let mut writer = SevenZWriter::create(path).unwrap();
+        writer.push_archive_entries(entries, SeqReader::new(readers)).unwrap();
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn skips_junk_in_a_solid_7z() {
+        let dir = TempDir::new("solid");
+        let archive = dir.0.join("mod.7z");
+        solid_7z(&archive, &[
+            ("Mod/__MACOSX/._mod.ini", "resource fork"),
+            ("Mod/Thumbs.db", "thumbnails"),
+            ("Mod/mod.ini", "[TextureOverrideBody]\nhash = 1234\n"),
+            ("Mod/../escape.txt", "outside"),
+            ("Mod/body.buf", "vertex data"),
+        ]);
+
+        let dest = dir.0.join("out");
+        extract(&archive, &dest, |_| Ok(())).unwrap();
+
+        assert_eq!(fs::read_to_string(dest.join("Mod/mod.ini")).unwrap(), "[TextureOverrideBody]\nhash = 1234\n");
+        assert_eq!(fs::read_to_string(dest.join("Mod/body.buf")).unwrap(), "vertex data");
+        assert!(!dest.join("Mod/Thumbs.db").exists());
+        assert!(!dest.join("Mod/__MACOSX").exists());
+        assert!(!dir.0.join("escape.txt").exists());
+    }
+}
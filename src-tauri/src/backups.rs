@@ -0,0 +1,281 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{get_game_db_path, load_game_db, schema, storage, strategy, write_game_db, Database, Mod};
+
+const MAX_SNAPSHOTS: usize = 20;
+
+// Saves arrive on every small UI change, so only snapshot when the newest
+// snapshot is older than this.
+const SNAPSHOT_INTERVAL_MS: u64 = 5 * 60 * 1000;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub created_at: u64,
+    pub mods: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ModSummary {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct EnabledChange {
+    pub id: i64,
+    pub name: String,
+    pub backup_enabled: bool,
+    pub current_enabled: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BackupDiff {
+    pub added: Vec<ModSummary>,
+    pub removed: Vec<ModSummary>,
+    pub enabled_changed: Vec<EnabledChange>,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn backups_dir(path: &Path) -> PathBuf {
+    path.parent().unwrap_or(Path::new(".")).join("backups")
+}
+
+fn snapshot_prefix(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    format!("{}-", stem)
+}
+
+/// Snapshots of `path` in the backups directory, newest first.
+pub fn snapshot_files(path: &Path) -> Vec<(u64, PathBuf)> {
+    let prefix = snapshot_prefix(path);
+    let entries = match fs::read_dir(backups_dir(path)) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut snapshots: Vec<(u64, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let timestamp = name.strip_prefix(&prefix)?.strip_suffix(".json")?.parse().ok()?;
+            Some((timestamp, entry.path()))
+        })
+        .collect();
+
+    snapshots.sort_by_key(|(timestamp, _)| std::cmp::Reverse(*timestamp));
+    snapshots
+}
+
+/// Copies the current contents of `path` into the backups directory and
+/// drops the oldest snapshots beyond `MAX_SNAPSHOTS`.
+pub fn snapshot(path: &Path, force: bool) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let now = now_millis();
+    let existing = snapshot_files(path);
+
+    if let Some((newest, newest_path)) = existing.first() {
+        if !force && now.saturating_sub(*newest) < SNAPSHOT_INTERVAL_MS {
+            return Ok(());
+        }
+        // Nothing changed since the last snapshot
+        if fs::read(newest_path).ok() == fs::read(path).ok() {
+            return Ok(());
+        }
+    }
+
+    let dir = backups_dir(path);
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create backups folder: {}", e))?;
+
+    let content = fs::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let snapshot_path = dir.join(format!("{}{}.json", snapshot_prefix(path), now));
+    storage::write_atomic(&snapshot_path, &content)
+        .map_err(|e| format!("Failed to write backup: {}", e))?;
+
+    for (_, old) in snapshot_files(path).iter().skip(MAX_SNAPSHOTS) {
+        let _ = fs::remove_file(old);
+    }
+
+    Ok(())
+}
+
+pub fn remove_snapshots(path: &Path) {
+    for (_, snapshot) in snapshot_files(path) {
+        let _ = fs::remove_file(snapshot);
+    }
+}
+
+fn find_snapshot(game_id: i64, file_name: &str) -> Result<PathBuf, String> {
+    snapshot_files(&get_game_db_path(game_id))
+        .into_iter()
+        .map(|(_, path)| path)
+        .find(|path| path.file_name().is_some_and(|name| name == file_name))
+        .ok_or_else(|| format!("Backup not found: {}", file_name))
+}
+
+fn read_snapshot(path: &Path) -> Result<Database, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read backup: {}", e))?;
+    let value = serde_json::from_str(&content)
+        .map_err(|e| format!("Backup is not a valid game database: {}", e))?;
+    schema::upgrade(value, strategy::DEFAULT_STRATEGY)
+}
+
+#[tauri::command]
+pub fn list_backups(game_id: i64) -> Result<Vec<BackupInfo>, String> {
+    let backups = snapshot_files(&get_game_db_path(game_id))
+        .into_iter()
+        .map(|(created_at, path)| BackupInfo {
+            file_name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            created_at,
+            mods: read_snapshot(&path).map(|db| db.mods.len()).unwrap_or(0),
+        })
+        .collect();
+
+    Ok(backups)
+}
+
+#[tauri::command]
+pub fn diff_backup(game_id: i64, file_name: String) -> Result<BackupDiff, String> {
+    let backup = read_snapshot(&find_snapshot(game_id, &file_name)?)?;
+    let current = load_game_db(game_id)?;
+
+    let backup_mods: HashMap<i64, &Mod> = backup.mods.iter().map(|m| (m.id, m)).collect();
+    let current_mods: HashMap<i64, &Mod> = current.mods.iter().map(|m| (m.id, m)).collect();
+
+    let added = current.mods.iter()
+        .filter(|m| !backup_mods.contains_key(&m.id))
+        .map(|m| ModSummary { id: m.id, name: m.name.clone() })
+        .collect();
+
+    let removed = backup.mods.iter()
+        .filter(|m| !current_mods.contains_key(&m.id))
+        .map(|m| ModSummary { id: m.id, name: m.name.clone() })
+        .collect();
+
+    let enabled_changed = current.mods.iter()
+        .filter_map(|m| {
+            let old = backup_mods.get(&m.id)?;
+            (old.enabled != m.enabled).then(|| EnabledChange {
+                id: m.id,
+                name: m.name.clone(),
+                backup_enabled: old.enabled,
+                current_enabled: m.enabled,
+            })
+        })
+        .collect();
+
+    Ok(BackupDiff { added, removed, enabled_changed })
+}
+
+#[tauri::command]
+pub fn restore_backup(game_id: i64, file_name: String) -> Result<(), String> {
+    let db = read_snapshot(&find_snapshot(game_id, &file_name)?)?;
+
+    // Keep the state being replaced so a restore can itself be undone
+    snapshot(&get_game_db_path(game_id), true)?;
+    write_game_db(game_id, db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("wwmm-backups-{}-{}", label, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const MINUTE_MS: u64 = 60 * 1000;
+
+    // A database file with one older snapshot taken `age` ago
+    fn database(dir: &TempDir, age: u64) -> (PathBuf, u64) {
+        let path = dir.0.join("game-1.json");
+        fs::write(&path, "{\"version\": 2}").unwrap();
+
+        let taken = now_millis() - age;
+        fs::create_dir_all(backups_dir(&path)).unwrap();
+        fs::write(backups_dir(&path).join(format!("game-1-{}.json", taken)), "{\"version\": 1}").unwrap();
+        (path, taken)
+    }
+
+    fn timestamps(path: &Path) -> Vec<u64> {
+        snapshot_files(path).into_iter().map(|(timestamp, _)| timestamp).collect()
+    }
+
+    #[test]
+    fn waits_five_minutes_between_snapshots() {
+        let dir = TempDir::new("interval");
+        let (path, taken) = database(&dir, 4 * MINUTE_MS);
+
+        snapshot(&path, false).unwrap();
+        assert_eq!(timestamps(&path), [taken]);
+
+        // A restore forces one to keep the state it replaces
+        snapshot(&path, true).unwrap();
+        let files = snapshot_files(&path);
+        assert_eq!(files.len(), 2);
+        assert_eq!(fs::read_to_string(&files[0].1).unwrap(), "{\"version\": 2}");
+    }
+
+    #[test]
+    fn snapshots_once_the_newest_is_old_enough() {
+        let dir = TempDir::new("old-enough");
+        let (path, taken) = database(&dir, 6 * MINUTE_MS);
+
+        snapshot(&path, false).unwrap();
+        let files = timestamps(&path);
+        assert_eq!(files.len(), 2);
+        assert!(files[0] > taken);
+
+        // Unchanged since the newest snapshot, even when forced
+        snapshot(&path, true).unwrap();
+        assert_eq!(timestamps(&path).len(), 2);
+    }
+
+    #[test]
+    fn keeps_only_the_newest_snapshots() {
+        let dir = TempDir::new("rotation");
+        let (path, _) = database(&dir, 6 * MINUTE_MS);
+        let oldest = now_millis() - 60 * MINUTE_MS;
+        for i in 0..MAX_SNAPSHOTS as u64 {
+            fs::write(backups_dir(&path).join(format!("game-1-{}.json", oldest + i)), "{}").unwrap();
+        }
+        // Snapshots of another game are left alone
+        fs::write(backups_dir(&path).join("game-2-1000.json"), "{}").unwrap();
+
+        snapshot(&path, false).unwrap();
+
+        let kept = timestamps(&path);
+        assert_eq!(kept.len(), MAX_SNAPSHOTS);
+        assert!(!kept.contains(&oldest) && !kept.contains(&(oldest + 1)));
+        assert!(backups_dir(&path).join("game-2-1000.json").exists());
+    }
+}
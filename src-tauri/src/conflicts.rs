@@ -0,0 +1,304 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::migoto::{self, HashOverride, KeyBinding};
+use crate::{load_game_db, Database, Mod};
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct HashUser {
+    pub mod_id: i64,
+    pub mod_name: String,
+    pub section: String,
+    pub file: String,
+    pub match_first_index: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HashConflict {
+    pub hash: String,
+    pub match_first_index: Option<String>,
+    pub users: Vec<HashUser>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct KeyUser {
+    pub mod_id: i64,
+    pub mod_name: String,
+    pub binding: KeyBinding,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct KeyConflict {
+    pub key: String,
+    pub users: Vec<KeyUser>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ConflictReport {
+    pub hashes: Vec<HashConflict>,
+    pub keys: Vec<KeyConflict>,
+    // Mods whose ini files could not be read
+    pub errors: Vec<String>,
+}
+
+// Parses the live ini files of every enabled mod, calling `visit` for each.
+fn for_each_enabled_ini(db: &Database, errors: &mut Vec<String>, mut visit: impl FnMut(&Mod, &str, &migoto::IniFile)) {
+    for m in db.mods.iter().filter(|m| m.enabled) {
+        let mod_path = db.mod_context(&m.name).mod_path();
+
+        for path in migoto::ini_files(&mod_path) {
+            if migoto::is_disabled_ini(&path) {
+                continue;
+            }
+
+            match migoto::parse_file(&path) {
+                Ok(ini) => {
+                    let file = path.strip_prefix(&mod_path).unwrap_or(&path).to_string_lossy().to_string();
+                    visit(m, &file, &ini);
+                }
+                Err(e) => errors.push(format!("{}: Failed to read {}: {}", m.name, path.display(), e)),
+            }
+        }
+    }
+}
+
+// Overrides of one hash only fight when they match the same draw call: the
+// same `match_first_index`, or one of them matching every index.
+fn hash_conflicts(hash: &str, users: &[HashUser]) -> Vec<HashConflict> {
+    let mut indices: Vec<Option<String>> = users.iter()
+        .filter_map(|u| u.match_first_index.clone())
+        .map(Some)
+        .collect();
+    indices.dedup();
+    if indices.is_empty() {
+        indices.push(None);
+    }
+
+    let mut conflicts: Vec<HashConflict> = Vec::new();
+    for index in indices {
+        let overlapping: Vec<HashUser> = users.iter()
+            .filter(|u| u.match_first_index.is_none() || u.match_first_index == index)
+            .cloned()
+            .collect();
+
+        let mut mod_ids: Vec<i64> = overlapping.iter().map(|u| u.mod_id).collect();
+        mod_ids.sort();
+        mod_ids.dedup();
+
+        if mod_ids.len() > 1 && !conflicts.iter().any(|c| c.users == overlapping) {
+            conflicts.push(HashConflict {
+                hash: hash.to_string(),
+                match_first_index: index,
+                users: overlapping,
+            });
+        }
+    }
+
+    conflicts
+}
+
+// Bindings of one key that another mod's binding of the same key can
+// trigger at the same time. `no_ctrl 7` and `ctrl 7` never fire together.
+fn key_conflict(key: &str, users: Vec<KeyUser>) -> Option<KeyConflict> {
+    let clashing: Vec<KeyUser> = users.iter()
+        .filter(|a| users.iter().any(|b| b.mod_id != a.mod_id && a.binding.combo.overlaps(&b.binding.combo)))
+        .cloned()
+        .collect();
+
+    (!clashing.is_empty()).then(|| KeyConflict {
+        key: key.to_string(),
+        users: clashing,
+    })
+}
+
+#[tauri::command]
+pub fn detect_conflicts(game_id: i64) -> Result<ConflictReport, String> {
+    let db = load_game_db(game_id)?;
+    let mut report = ConflictReport::default();
+
+    let mut by_hash: BTreeMap<String, Vec<HashUser>> = BTreeMap::new();
+    let mut by_key: BTreeMap<String, Vec<KeyUser>> = BTreeMap::new();
+    for_each_enabled_ini(&db, &mut report.errors, |m, file, ini| {
+        for binding in ini.key_bindings(file) {
+            by_key.entry(binding.combo.key.clone()).or_default().push(KeyUser {
+                mod_id: m.id,
+                mod_name: m.name.clone(),
+                binding,
+            });
+        }
+
+        for HashOverride { hash, section, match_first_index, file, .. } in ini.hashes(file) {
+            by_hash.entry(hash).or_default().push(HashUser {
+                mod_id: m.id,
+                mod_name: m.name.clone(),
+                section,
+                file,
+                match_first_index,
+            });
+        }
+    });
+
+    for (hash, mut users) in by_hash {
+        users.sort_by(|a, b| a.match_first_index.cmp(&b.match_first_index));
+        report.hashes.extend(hash_conflicts(&hash, &users));
+    }
+
+    report.keys = by_key.into_iter()
+        .filter(|(key, _)| !key.is_empty())
+        .filter_map(|(key, users)| key_conflict(&key, users))
+        .collect();
+
+    Ok(report)
+}
+
+/// Keys a single mod listens for. Disabled mods report the keys they would
+/// bind once enabled.
+#[tauri::command]
+pub fn list_mod_keys(game_id: i64, name: String) -> Result<Vec<KeyBinding>, String> {
+    let db = load_game_db(game_id)?;
+    let mod_path = db.mod_folder(&name)
+        .ok_or_else(|| format!("Mod folder not found: {}", name))?;
+
+    let mut keys = Vec::new();
+    for path in migoto::active_ini_files(&mod_path) {
+        let ini = migoto::parse_file(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let file = path.strip_prefix(&mod_path).unwrap_or(&path).to_string_lossy().to_string();
+        keys.extend(ini.key_bindings(&file));
+    }
+
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestDataDir;
+    use crate::write_game_db;
+    use serde_json::json;
+    use std::fs;
+    use std::path::Path;
+
+    // Name, whether it is enabled, and the (path, content) of its ini files
+    type TestMod<'a> = (&'a str, bool, &'a [(&'a str, &'a str)]);
+
+    // Enabled mods go in the mod folder and disabled ones in `_Disabled`
+    fn game(data: &TestDataDir, mods: &[TestMod]) {
+        let root = data.0.join("Mods");
+        let records: Vec<serde_json::Value> = mods.iter()
+            .enumerate()
+            .map(|(i, (name, enabled, files))| {
+                let folder = if *enabled { root.join(name) } else { root.join("_Disabled").join(name) };
+                for (file, content) in files.iter() {
+                    let path = folder.join(file);
+                    fs::create_dir_all(path.parent().unwrap()).unwrap();
+                    fs::write(path, content).unwrap();
+                }
+                json!({
+                    "id": i as i64 + 1,
+                    "name": name,
+                    "category_id": 0,
+                    "enabled": enabled,
+                    "notes": "",
+                    "preview": null,
+                    "tags": [],
+                })
+            })
+            .collect();
+
+        let db: Database = serde_json::from_value(json!({
+            "root_folder": root,
+            "disabled_folder": "_Disabled",
+            "mod_strategy": "generic_rename",
+            "categories": [],
+            "mods": records,
+            "tags": [],
+            "tag_metadata": [],
+            "profiles": [],
+        })).unwrap();
+        write_game_db(1, db).unwrap();
+    }
+
+    fn users(conflict: &HashConflict) -> Vec<(&str, Option<&str>)> {
+        conflict.users.iter()
+            .map(|u| (u.mod_name.as_str(), u.match_first_index.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn reports_overrides_of_the_same_draw_call() {
+        let data = TestDataDir::new("conflicts-hashes");
+        game(&data, &[
+            ("A", true, &[("mod.ini", "[TextureOverrideBody]\nhash = ABCDEF12\nmatch_first_index = 0\n\
+                [TextureOverrideFace]\nhash = 11111111\n\
+                [TextureOverrideFaceAgain]\nhash = 11111111\n")]),
+            ("B", true, &[("Body/body.ini", "[TextureOverrideBody]\nhash = abcdef12\nmatch_first_index = 0\n\
+                [TextureOverrideFace]\nhash = 11111111\nmatch_first_index = 3\n")]),
+            // Another index of the same buffer is a different draw call
+            ("C", true, &[("mod.ini", "[TextureOverrideHair]\nhash = abcdef12\nmatch_first_index = 6\n")]),
+            ("D", false, &[("mod.ini", "[TextureOverrideBody]\nhash = abcdef12\nmatch_first_index = 0\n")]),
+            ("E", true, &[("DISABLED_old.ini", "[TextureOverrideBody]\nhash = abcdef12\nmatch_first_index = 0\n")]),
+        ]);
+
+        let report = detect_conflicts(1).unwrap();
+
+        assert!(report.errors.is_empty());
+        let hashes: Vec<(&str, Option<&str>)> = report.hashes.iter()
+            .map(|c| (c.hash.as_str(), c.match_first_index.as_deref()))
+            .collect();
+        assert_eq!(hashes, [("11111111", Some("3")), ("abcdef12", Some("0"))]);
+        // A face override without an index matches B's index 3 as well
+        assert_eq!(users(&report.hashes[0]), [("A", None), ("A", None), ("B", Some("3"))]);
+        assert_eq!(users(&report.hashes[1]), [("A", Some("0")), ("B", Some("0"))]);
+        assert_eq!(report.hashes[1].users[1].file, Path::new("Body").join("body.ini").to_string_lossy());
+    }
+
+    #[test]
+    fn ignores_overrides_within_one_mod() {
+        let data = TestDataDir::new("conflicts-own");
+        game(&data, &[
+            ("A", true, &[
+                ("mod.ini", "[TextureOverrideBody]\nhash = abcdef12\n"),
+                ("Variant/variant.ini", "[TextureOverrideBody]\nhash = abcdef12\n"),
+            ]),
+        ]);
+
+        assert!(detect_conflicts(1).unwrap().hashes.is_empty());
+    }
+
+    #[test]
+    fn reports_keys_pressed_together() {
+        let data = TestDataDir::new("conflicts-keys");
+        game(&data, &[
+            ("A", true, &[("mod.ini", "[KeyCycle]\nkey = no_modifiers VK_F1\n")]),
+            ("B", true, &[("mod.ini", "[KeyCycle]\nkey = ctrl VK_F1\n[KeyToggle]\nkey = F2\n")]),
+            ("C", true, &[("mod.ini", "[KeySwap]\nkey = VK_F2\n")]),
+        ]);
+
+        let report = detect_conflicts(1).unwrap();
+
+        let keys: Vec<(&str, Vec<&str>)> = report.keys.iter()
+            .map(|c| (c.key.as_str(), c.users.iter().map(|u| u.mod_name.as_str()).collect()))
+            .collect();
+        assert_eq!(keys, [("F2", vec!["B", "C"])]);
+    }
+
+    #[test]
+    fn lists_keys_of_a_disabled_mod() {
+        let data = TestDataDir::new("conflicts-list");
+        game(&data, &[
+            ("A", false, &[("mod.ini.bak", "[KeyCycle]\nkey = VK_F5\n")]),
+            ("B", true, &[
+                ("mod.ini", "[KeyCycle]\nkey = F6\n"),
+                ("old.ini.bak", "[KeyCycle]\nkey = F7\n"),
+            ]),
+        ]);
+
+        let labels = |name: &str| -> Vec<String> {
+            list_mod_keys(1, name.to_string()).unwrap().into_iter().map(|k| k.combo.label).collect()
+        };
+        assert_eq!(labels("A"), ["F5"]);
+        assert_eq!(labels("B"), ["F6"]);
+        assert!(list_mod_keys(1, "Missing".to_string()).is_err());
+    }
+}
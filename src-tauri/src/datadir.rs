@@ -0,0 +1,322 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{schema, strategy, transfer, Game};
+
+// Folders inside the data directory that belong to the manager.
+const DATA_DIRS: &[&str] = &["backups", "history", "journals", "trash"];
+
+// Where migrated data is copied before it replaces the data already there,
+// and where that data is set aside until the new data is in place.
+const STAGING_DIR: &str = ".migrating";
+const REPLACED_DIR: &str = ".replaced";
+
+fn is_data_file(name: &str) -> bool {
+    let name = name.strip_suffix(".bak").unwrap_or(name);
+
+    if name == "games.json" || name == "mod-manager.json" {
+        return true;
+    }
+
+    name.strip_prefix("game-")
+        .and_then(|rest| rest.strip_suffix(".json"))
+        .is_some_and(|id| id.parse::<i64>().is_ok())
+}
+
+/// Every file and folder of manager data in `dir`. The local location is
+/// the working directory, so anything not recognised is left alone.
+pub fn data_entries(dir: &Path) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut found: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.path().is_dir() {
+                DATA_DIRS.contains(&name.as_str())
+            } else {
+                is_data_file(&name)
+            }
+        })
+        .map(|entry| entry.path())
+        .collect();
+
+    found.sort();
+    found
+}
+
+pub fn has_data(dir: &Path) -> bool {
+    data_entries(dir).iter().any(|path| {
+        path.file_name().is_some_and(|name| name == "games.json" || name == "mod-manager.json")
+    })
+}
+
+pub fn copy_data(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to)
+        .map_err(|e| format!("Failed to create data folder: {}", e))?;
+
+    for entry in data_entries(from) {
+        let name = entry.file_name().unwrap_or_default();
+        let target = to.join(name);
+
+        if entry.is_dir() {
+            transfer::copy_dir(&entry, &target, &mut |_| Ok(()))
+        } else {
+            fs::copy(&entry, &target).map(|_| ()).map_err(|e| e.to_string())
+        }
+        .map_err(|e| format!("Failed to copy {}: {}", entry.display(), e))?;
+    }
+
+    Ok(())
+}
+
+fn move_entries(entries: &[PathBuf], to: &Path) -> Result<(), String> {
+    for entry in entries {
+        let name = entry.file_name().unwrap_or_default();
+        fs::rename(entry, to.join(name))
+            .map_err(|e| format!("Failed to move {}: {}", entry.display(), e))?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the data in `to` with a copy of the data in `from`. The copy is
+/// made in a staging folder first, so the data in `to` is only touched once
+/// the copy has succeeded, and is put back if renaming the copy into place
+/// fails.
+pub fn replace_data(from: &Path, to: &Path) -> Result<(), String> {
+    let staging = to.join(STAGING_DIR);
+    let replaced = to.join(REPLACED_DIR);
+    if replaced.exists() {
+        return Err(format!("Data left by an earlier failed move is still in {}", replaced.display()));
+    }
+
+    let _ = fs::remove_dir_all(&staging);
+    if let Err(e) = copy_data(from, &staging).and_then(|_| {
+        fs::create_dir_all(&replaced).map_err(|e| format!("Failed to create data folder: {}", e))
+    }) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+
+    let result = move_entries(&data_entries(to), &replaced).and_then(|_| {
+        move_entries(&data_entries(&staging), to).inspect_err(|_| {
+            // Only new data is left in `to` once the old data is set aside
+            let _ = remove_data(to);
+        })
+    });
+    let _ = fs::remove_dir_all(&staging);
+
+    if let Err(e) = result {
+        // Old data that can't be put back stays where it was set aside
+        return match move_entries(&data_entries(&replaced), to) {
+            Ok(()) => {
+                let _ = fs::remove_dir_all(&replaced);
+                Err(e)
+            }
+            Err(_) => Err(format!("{}; the previous data was left in {}", e, replaced.display())),
+        };
+    }
+
+    let _ = fs::remove_dir_all(&replaced);
+    Ok(())
+}
+
+pub fn remove_data(dir: &Path) -> Result<(), String> {
+    for entry in data_entries(dir) {
+        if entry.is_dir() {
+            fs::remove_dir_all(&entry)
+        } else {
+            fs::remove_file(&entry)
+        }
+        .map_err(|e| format!("Failed to delete {}: {}", entry.display(), e))?;
+    }
+
+    Ok(())
+}
+
+/// Copies the data in `dir` into a timestamped `discarded-*` folder next to
+/// it, so it survives being replaced or deleted.
+pub fn backup_data(dir: &Path) -> Result<PathBuf, String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let backup_dir = dir.join(format!("discarded-{}", timestamp));
+
+    // A partial backup would look like a complete one
+    if let Err(e) = copy_data(dir, &backup_dir) {
+        let _ = fs::remove_dir_all(&backup_dir);
+        return Err(e);
+    }
+    Ok(backup_dir)
+}
+
+fn read_database(path: &Path, default_strategy: &str) -> Option<crate::Database> {
+    let content = fs::read_to_string(path).ok()?;
+    let value: Value = serde_json::from_str(&content).ok()?;
+    schema::upgrade(value, default_strategy).ok()
+}
+
+/// Totals across every game database in `dir`, plus the legacy database
+/// if it has not been imported yet.
+pub fn summarize(dir: &Path) -> Value {
+    let games: Vec<Game> = fs::read_to_string(dir.join("games.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    let mut databases: Vec<crate::Database> = games.iter()
+        .filter_map(|game| read_database(&dir.join(format!("game-{}.json", game.id)), strategy::DEFAULT_STRATEGY))
+        .collect();
+    databases.extend(read_database(&dir.join("mod-manager.json"), strategy::LEGACY_STRATEGY));
+
+    let mut roots: Vec<&str> = Vec::new();
+    let mut strategies: Vec<&str> = Vec::new();
+    for db in &databases {
+        if !roots.contains(&db.root_folder.as_str()) {
+            roots.push(&db.root_folder);
+        }
+        if !strategies.contains(&db.mod_strategy.as_str()) {
+            strategies.push(&db.mod_strategy);
+        }
+    }
+
+    json!({
+        "games": games.len(),
+        "categories": databases.iter().map(|db| db.categories.len()).sum::<usize>(),
+        "mods": databases.iter().map(|db| db.mods.len()).sum::<usize>(),
+        "root_folder": roots.join(", "),
+        "mod_strategy": strategies.join(", ")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("wwmm-datadir-{}-{}", label, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    // Data in `old` and `new`, plus a file of someone else's in `old`
+    fn locations(dir: &TempDir) -> (PathBuf, PathBuf) {
+        let old = dir.0.join("old");
+        write(&old.join("games.json"), "old games");
+        write(&old.join("game-2.json"), "old game");
+        write(&old.join("trash/1/entry.json"), "old trash");
+        write(&old.join("notes.txt"), "not ours");
+
+        let new = dir.0.join("new");
+        write(&new.join("games.json"), "new games");
+        write(&new.join("game-1.json"), "new game");
+        write(&new.join("history/1.json"), "new history");
+        (old, new)
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir).unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn replaces_only_manager_data() {
+        let dir = TempDir::new("replace");
+        let (old, new) = locations(&dir);
+
+        replace_data(&new, &old).unwrap();
+
+        assert_eq!(names(&old), ["game-1.json", "games.json", "history", "notes.txt"]);
+        assert_eq!(read(&old.join("games.json")), "new games");
+        assert_eq!(read(&old.join("history/1.json")), "new history");
+        assert_eq!(read(&old.join("notes.txt")), "not ours");
+        assert_eq!(read(&new.join("games.json")), "new games");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_copy_leaves_the_old_data() {
+        let dir = TempDir::new("failed");
+        let (old, new) = locations(&dir);
+        // A link to nothing can't be copied
+        std::os::unix::fs::symlink(dir.0.join("missing"), new.join("game-3.json")).unwrap();
+
+        assert!(replace_data(&new, &old).is_err());
+        assert_eq!(names(&old), ["game-2.json", "games.json", "notes.txt", "trash"]);
+        assert_eq!(read(&old.join("games.json")), "old games");
+        assert_eq!(read(&old.join("trash/1/entry.json")), "old trash");
+
+        assert!(backup_data(&new).is_err());
+        assert_eq!(names(&new), ["game-1.json", "game-3.json", "games.json", "history"]);
+    }
+
+    #[test]
+    fn discards_a_stale_staging_folder() {
+        let dir = TempDir::new("staging");
+        let (old, new) = locations(&dir);
+        write(&old.join(STAGING_DIR).join("game-9.json"), "half copied");
+
+        replace_data(&new, &old).unwrap();
+
+        assert!(!old.join("game-9.json").exists());
+        assert!(!old.join(STAGING_DIR).exists());
+        assert_eq!(read(&old.join("games.json")), "new games");
+    }
+
+    #[test]
+    fn refuses_while_replaced_data_is_left_over() {
+        let dir = TempDir::new("replaced");
+        let (old, new) = locations(&dir);
+        write(&old.join(REPLACED_DIR).join("games.json"), "older games");
+
+        let error = replace_data(&new, &old).unwrap_err();
+
+        assert!(error.starts_with("Data left by an earlier failed move"), "{}", error);
+        assert_eq!(read(&old.join("games.json")), "old games");
+        assert_eq!(read(&old.join(REPLACED_DIR).join("games.json")), "older games");
+        assert!(!old.join("game-1.json").exists());
+    }
+
+    #[test]
+    fn backs_up_manager_data() {
+        let dir = TempDir::new("backup");
+        let (old, _) = locations(&dir);
+
+        let backup = backup_data(&old).unwrap();
+
+        assert_eq!(names(&backup), ["game-2.json", "games.json", "trash"]);
+        assert_eq!(read(&backup.join("trash/1/entry.json")), "old trash");
+        assert_eq!(read(&old.join("games.json")), "old games");
+    }
+}
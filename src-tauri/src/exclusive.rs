@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+
+use crate::journal::Journal;
+use crate::strategy::ModStrategy;
+use crate::{apply_toggle, Database, ExclusiveMode};
+
+/// One mod whose state was changed by a toggle.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModChange {
+    pub name: String,
+    pub enabled: bool,
+    // Set when the mod was disabled because it shares a mutually exclusive
+    // tag with the mod that was enabled
+    pub exclusive_tag: Option<String>,
+    pub caused_by: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ToggleReport {
+    pub changes: Vec<ModChange>,
+    pub errors: Vec<String>,
+}
+
+struct Conflict {
+    tag: String,
+    mode: ExclusiveMode,
+    mods: Vec<String>,
+}
+
+// Enabled mods that share a mutually exclusive tag with `name`, grouped by tag.
+fn conflicts(db: &Database, name: &str) -> Vec<Conflict> {
+    let tags = match db.mods.iter().find(|m| m.name == name) {
+        Some(m) => &m.tags,
+        None => return vec![],
+    };
+
+    db.tag_metadata.iter()
+        .filter(|meta| meta.mutually_exclusive && tags.contains(&meta.name))
+        .filter_map(|meta| {
+            let mods: Vec<String> = db.mods.iter()
+                .filter(|m| m.enabled && m.name != name && m.tags.contains(&meta.name))
+                .map(|m| m.name.clone())
+                .collect();
+
+            (!mods.is_empty()).then(|| Conflict {
+                tag: meta.name.clone(),
+                mode: meta.exclusive_mode,
+                mods,
+            })
+        })
+        .collect()
+}
+
+fn set_enabled(
+    db: &mut Database,
+    strategy: &dyn ModStrategy,
+    name: &str,
+    enable: bool,
+    journal: Option<&mut Journal>
+) -> Result<bool, String> {
+    let toggled = match journal {
+        Some(journal) => journal.toggle(strategy, name, enable)?,
+        None => apply_toggle(strategy, &db.mod_context(name), enable).map(|_| true)?,
+    };
+
+    if let Some(m) = db.mods.iter_mut().find(|m| m.name == name) {
+        m.enabled = enable;
+    }
+    Ok(toggled)
+}
+
+/// Toggles `name` within a game, enforcing the game's mutually exclusive
+/// tags. Every mod whose state changed is recorded in `db` and appended to
+/// `changes`. With a `journal`, each of those toggles is recorded in it
+/// first, mods already in the requested state are left out, and mods changed
+/// before an error stay changed for the journal to roll back. Without one,
+/// they are put back before the error is returned, and only those that
+/// could not be are left in `changes`.
+pub fn toggle(
+    db: &mut Database,
+    strategy: &dyn ModStrategy,
+    name: &str,
+    enable: bool,
+    changes: &mut Vec<ModChange>,
+    journal: Option<&mut Journal>
+) -> Result<(), String> {
+    let start = changes.len();
+    let journaled = journal.is_some();
+
+    let result = toggle_with_conflicts(db, strategy, name, enable, changes, journal);
+    if journaled {
+        return result;
+    }
+
+    result.map_err(|e| {
+        let mut errors = vec![e];
+        let done: Vec<ModChange> = changes.drain(start..).collect();
+
+        // A mod that can't be put back stays listed as changed
+        for change in done.into_iter().rev() {
+            if let Err(e) = set_enabled(db, strategy, &change.name, !change.enabled, None) {
+                errors.push(format!("Failed to restore {}: {}", change.name, e));
+                changes.insert(start, change);
+            }
+        }
+        errors.join("\n")
+    })
+}
+
+fn toggle_with_conflicts(
+    db: &mut Database,
+    strategy: &dyn ModStrategy,
+    name: &str,
+    enable: bool,
+    changes: &mut Vec<ModChange>,
+    mut journal: Option<&mut Journal>
+) -> Result<(), String> {
+    if enable {
+        let conflicts = conflicts(db, name);
+
+        // Refuse before touching anything, even if other tags would auto-disable
+        if let Some(refused) = conflicts.iter().find(|c| c.mode == ExclusiveMode::Refuse) {
+            return Err(format!(
+                "Cannot enable {}: tag \"{}\" is mutually exclusive with {}",
+                name, refused.tag, refused.mods.join(", ")
+            ));
+        }
+
+        for conflict in conflicts {
+            for other in conflict.mods {
+                // Already disabled for an earlier tag
+                if !db.mods.iter().any(|m| m.name == other && m.enabled) {
+                    continue;
+                }
+
+                let toggled = set_enabled(db, strategy, &other, false, journal.as_deref_mut())
+                    .map_err(|e| format!("Failed to disable {}: {}", other, e))?;
+                if toggled {
+                    changes.push(ModChange {
+                        name: other,
+                        enabled: false,
+                        exclusive_tag: Some(conflict.tag.clone()),
+                        caused_by: Some(name.to_string()),
+                    });
+                }
+            }
+        }
+    }
+
+    if set_enabled(db, strategy, name, enable, journal)? {
+        changes.push(ModChange {
+            name: name.to_string(),
+            enabled: enable,
+            exclusive_tag: None,
+            caused_by: None,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("wwmm-exclusive-{}-{}", label, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    // A generic_rename game whose mods all carry the tag "Outfit", which is
+    // mutually exclusive in `mode`.
+    fn game(dir: &Path, mode: &str, mods: &[(&str, bool)]) -> Database {
+        let root = dir.join("Mods");
+        fs::create_dir_all(root.join("_Disabled")).unwrap();
+
+        let records: Vec<serde_json::Value> = mods.iter()
+            .enumerate()
+            .map(|(i, (name, enabled))| {
+                let folder = if *enabled { root.join(name) } else { root.join("_Disabled").join(name) };
+                fs::create_dir_all(&folder).unwrap();
+                fs::write(folder.join("mod.ini"), "[Constants]\n").unwrap();
+
+                serde_json::json!({
+                    "id": i + 1,
+                    "name": name,
+                    "category_id": 0,
+                    "enabled": enabled,
+                    "notes": "",
+                    "preview": null,
+                    "tags": ["Outfit"],
+                })
+            })
+            .collect();
+
+        serde_json::from_value(serde_json::json!({
+            "root_folder": root,
+            "disabled_folder": "_Disabled",
+            "mod_strategy": "generic_rename",
+            "categories": [],
+            "mods": records,
+            "tags": ["Outfit"],
+            "tag_metadata": [{
+                "name": "Outfit",
+                "description": "",
+                "preview": null,
+                "mutually_exclusive": true,
+                "exclusive_mode": mode,
+            }],
+            "profiles": [],
+        })).unwrap()
+    }
+
+    // Each mod's state as recorded in `db` and as found on disk.
+    fn states(db: &Database) -> Vec<(bool, bool)> {
+        let root = Path::new(&db.root_folder);
+        db.mods.iter().map(|m| (m.enabled, root.join(&m.name).exists())).collect()
+    }
+
+    #[test]
+    fn auto_disables_other_mods() {
+        let dir = TempDir::new("auto");
+        let mut db = game(&dir.0, "auto_disable", &[("A", true), ("B", false)]);
+        let mut changes = Vec::new();
+
+        toggle(&mut db, strategy::get("generic_rename").unwrap(), "B", true, &mut changes, None).unwrap();
+
+        assert_eq!(states(&db), [(false, false), (true, true)]);
+        let changed: Vec<(&str, Option<&str>)> = changes.iter()
+            .map(|c| (c.name.as_str(), c.caused_by.as_deref()))
+            .collect();
+        assert_eq!(changed, [("A", Some("B")), ("B", None)]);
+    }
+
+    #[test]
+    fn refused_toggle_changes_nothing() {
+        let dir = TempDir::new("refuse");
+        let mut db = game(&dir.0, "refuse", &[("A", true), ("B", false)]);
+        let mut changes = Vec::new();
+
+        let result = toggle(&mut db, strategy::get("generic_rename").unwrap(), "B", true, &mut changes, None);
+
+        assert!(result.unwrap_err().contains("mutually exclusive with A"));
+        assert!(changes.is_empty());
+        assert_eq!(states(&db), [(true, true), (false, false)]);
+    }
+
+    #[test]
+    fn failed_auto_disable_restores_mods_already_disabled() {
+        let dir = TempDir::new("restore");
+        let mut db = game(&dir.0, "auto_disable", &[("A", true), ("B", false), ("C", true)]);
+        // A leftover folder in the way makes disabling C fail after A is done
+        fs::create_dir_all(dir.0.join("Mods/_Disabled/C/leftover")).unwrap();
+        let mut changes = Vec::new();
+
+        let result = toggle(&mut db, strategy::get("generic_rename").unwrap(), "B", true, &mut changes, None);
+
+        assert!(result.unwrap_err().starts_with("Failed to disable C"));
+        assert!(changes.is_empty());
+        assert_eq!(states(&db), [(true, true), (false, false), (true, true)]);
+    }
+}
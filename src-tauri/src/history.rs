@@ -0,0 +1,887 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::exclusive::ModChange;
+use crate::strategy::ModStrategy;
+use crate::watcher::ModWatcher;
+use crate::{apply_toggle, get_data_dir, load_game_db, paths, storage, strategy, transfer, trash, write_game_db, Database, Mod};
+
+const MAX_OPERATIONS: usize = 100;
+
+// Fields that follow the mod folder or the UI rather than an edit: whether a
+// mod is enabled is only ever changed by toggling it, its preview mirrors an
+// image file, and whether a category is expanded shouldn't be undoable.
+const MOD_STATE: &[&str] = &["enabled", "preview"];
+const CATEGORY_STATE: &[&str] = &["expanded"];
+
+/// Part of a game database, as it was before or after an edit.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DbPatch {
+    // Top-level fields other than the mod and category lists
+    #[serde(default)]
+    pub fields: Map<String, Value>,
+    // Mods and categories by id; `None` where one didn't exist
+    #[serde(default)]
+    pub mods: Vec<(i64, Option<Value>)>,
+    #[serde(default)]
+    pub categories: Vec<(i64, Option<Value>)>,
+}
+
+impl DbPatch {
+    fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.mods.is_empty() && self.categories.is_empty()
+    }
+}
+
+/// A mod deleted into the recycle bin.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeletedMod {
+    pub trash_id: String,
+    pub root: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    /// Mods toggled together, in order, with the state each was set to.
+    Toggle { mods: Vec<ModChange> },
+    /// A mod folder renamed inside `root`, along with its record.
+    Rename { root: String, old_name: String, new_name: String },
+    /// A folder moved into a mod folder, usually when importing it, with
+    /// the record added for it.
+    Move {
+        source: String,
+        destination: String,
+        #[serde(default)]
+        record: Option<Mod>,
+    },
+    Delete { mods: Vec<DeletedMod> },
+    /// An edit saved from the UI.
+    Edit { before: DbPatch, after: DbPatch },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Operation {
+    pub id: u64,
+    pub label: String,
+    pub at: u64,
+    pub change: Change,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct History {
+    next_id: u64,
+    undo: Vec<Operation>,
+    redo: Vec<Operation>,
+}
+
+/// An operation as listed in the UI.
+#[derive(Debug, Serialize, Clone)]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub label: String,
+    pub at: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HistoryInfo {
+    pub undo: Vec<HistoryEntry>,
+    pub redo: Vec<HistoryEntry>,
+}
+
+impl From<&Operation> for HistoryEntry {
+    fn from(operation: &Operation) -> Self {
+        HistoryEntry {
+            id: operation.id,
+            label: operation.label.clone(),
+            at: operation.at,
+        }
+    }
+}
+
+fn history_path(game_id: i64) -> PathBuf {
+    get_data_dir().join("history").join(format!("game-{}.json", game_id))
+}
+
+fn load(game_id: i64) -> History {
+    let path = history_path(game_id);
+    if !path.exists() {
+        return History::default();
+    }
+
+    storage::read_json(&path).unwrap_or_default()
+}
+
+fn save(game_id: i64, history: &History) -> Result<(), String> {
+    let path = history_path(game_id);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create history folder: {}", e))?;
+    }
+
+    storage::write_json(&path, history)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Adds an operation that has just been performed, dropping anything that
+/// could have been redone. The operation has already happened by then, and
+/// the error says so.
+pub fn record(game_id: i64, label: String, change: Change) -> Result<(), String> {
+    let mut history = load(game_id);
+    history.next_id += 1;
+    history.undo.push(Operation {
+        id: history.next_id,
+        label,
+        at: now(),
+        change,
+    });
+    history.redo.clear();
+
+    let excess = history.undo.len().saturating_sub(MAX_OPERATIONS);
+    history.undo.drain(..excess);
+
+    save(game_id, &history).map_err(not_recorded)
+}
+
+fn not_recorded(e: String) -> String {
+    format!("The change was made but could not be added to the undo history: {}", e)
+}
+
+/// Records a batch of toggles, named after the mods that were asked for.
+pub fn record_toggle(game_id: i64, requested: &[String], enable: bool, changes: &[ModChange]) -> Result<(), String> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let verb = if enable { "Enable" } else { "Disable" };
+    let label = match requested {
+        [name] => format!("{} {}", verb, name),
+        _ => format!("{} {} mods", verb, requested.len()),
+    };
+
+    record(game_id, label, Change::Toggle { mods: changes.to_vec() })
+}
+
+/// Records the difference between two versions of a game database, if
+/// there is one worth undoing. An edit of the same fields as the edit just
+/// before it, such as the next keystroke of a note, is merged into that one.
+pub fn record_edit(game_id: i64, before: &Value, after: &Value) -> Result<(), String> {
+    let (before, after) = diff(before, after);
+    if after.is_empty() {
+        return Ok(());
+    }
+
+    let mut history = load(game_id);
+    if merge_edit(&mut history, &before, &after) {
+        return save(game_id, &history).map_err(not_recorded);
+    }
+
+    let label = describe(&after);
+    record(game_id, label, Change::Edit { before, after })
+}
+
+// Folds an edit into the last operation when that one edited the same
+// fields and nothing has been undone since. Returns whether it did.
+fn merge_edit(history: &mut History, before: &DbPatch, after: &DbPatch) -> bool {
+    if !history.redo.is_empty() {
+        return false;
+    }
+    let Some(last) = history.undo.last_mut() else {
+        return false;
+    };
+    let Change::Edit { before: first, after: latest } = &mut last.change else {
+        return false;
+    };
+    if edited(first, latest) != edited(before, after) {
+        return false;
+    }
+
+    // Typing a value and then erasing it leaves nothing to undo
+    if edited(first, after).is_empty() {
+        history.undo.pop();
+    } else {
+        *latest = after.clone();
+        last.at = now();
+    }
+    true
+}
+
+pub fn forget(game_id: i64) {
+    let _ = storage::remove_json(&history_path(game_id));
+}
+
+// ============ DATABASE PATCHES ============
+
+fn without(value: &Value, keys: &[&str]) -> Value {
+    let mut value = value.clone();
+    if let Some(object) = value.as_object_mut() {
+        for key in keys {
+            object.remove(*key);
+        }
+    }
+    value
+}
+
+fn by_id(list: Option<&Value>) -> BTreeMap<i64, &Value> {
+    list.and_then(|list| list.as_array())
+        .map(|items| {
+            items.iter()
+                .filter_map(|item| Some((item.get("id")?.as_i64()?, item)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn diff_list(
+    before: Option<&Value>,
+    after: Option<&Value>,
+    state: &[&str],
+    old: &mut Vec<(i64, Option<Value>)>,
+    new: &mut Vec<(i64, Option<Value>)>
+) {
+    let before = by_id(before);
+    let after = by_id(after);
+    let ids: BTreeSet<&i64> = before.keys().chain(after.keys()).collect();
+
+    for id in ids {
+        let (was, is) = (before.get(id), after.get(id));
+        if was.map(|v| without(v, state)) != is.map(|v| without(v, state)) {
+            old.push((*id, was.map(|v| (*v).clone())));
+            new.push((*id, is.map(|v| (*v).clone())));
+        }
+    }
+}
+
+fn diff(before: &Value, after: &Value) -> (DbPatch, DbPatch) {
+    let mut old = DbPatch::default();
+    let mut new = DbPatch::default();
+    let empty = Map::new();
+    let before_fields = before.as_object().unwrap_or(&empty);
+    let after_fields = after.as_object().unwrap_or(&empty);
+
+    let keys: BTreeSet<&String> = before_fields.keys().chain(after_fields.keys()).collect();
+
+    for key in keys {
+        let (was, is) = (before_fields.get(key), after_fields.get(key));
+        match key.as_str() {
+            "mods" => diff_list(was, is, MOD_STATE, &mut old.mods, &mut new.mods),
+            "categories" => diff_list(was, is, CATEGORY_STATE, &mut old.categories, &mut new.categories),
+            _ if was != is => {
+                old.fields.insert(key.clone(), was.cloned().unwrap_or(Value::Null));
+                new.fields.insert(key.clone(), is.cloned().unwrap_or(Value::Null));
+            }
+            _ => {}
+        }
+    }
+
+    (old, new)
+}
+
+fn edited_list(
+    prefix: &str,
+    state: &[&str],
+    before: &[(i64, Option<Value>)],
+    after: &[(i64, Option<Value>)],
+    paths: &mut BTreeSet<String>
+) {
+    let before: BTreeMap<i64, &Option<Value>> = before.iter().map(|(id, v)| (*id, v)).collect();
+    let after: BTreeMap<i64, &Option<Value>> = after.iter().map(|(id, v)| (*id, v)).collect();
+    let ids: BTreeSet<&i64> = before.keys().chain(after.keys()).collect();
+
+    for id in ids {
+        match (before.get(id), after.get(id)) {
+            (Some(Some(Value::Object(was))), Some(Some(Value::Object(is)))) => {
+                let keys: BTreeSet<&String> = was.keys().chain(is.keys()).collect();
+                let changed = keys.into_iter()
+                    .filter(|key| !state.contains(&key.as_str()) && was.get(*key) != is.get(*key));
+                for key in changed {
+                    paths.insert(format!("{}/{}/{}", prefix, id, key));
+                }
+            }
+            (was, is) if was != is => {
+                paths.insert(format!("{}/{}", prefix, id));
+            }
+            _ => {}
+        }
+    }
+}
+
+// Every field an edit changes, such as "mods/3/notes", so that successive
+// edits of the same fields can be told apart from unrelated ones.
+fn edited(before: &DbPatch, after: &DbPatch) -> BTreeSet<String> {
+    let mut paths = BTreeSet::new();
+
+    let keys: BTreeSet<&String> = before.fields.keys().chain(after.fields.keys()).collect();
+    for key in keys.into_iter().filter(|key| before.fields.get(*key) != after.fields.get(*key)) {
+        paths.insert(format!("fields/{}", key));
+    }
+    edited_list("mods", MOD_STATE, &before.mods, &after.mods, &mut paths);
+    edited_list("categories", CATEGORY_STATE, &before.categories, &after.categories, &mut paths);
+
+    paths
+}
+
+fn describe(patch: &DbPatch) -> String {
+    let mut parts = Vec::new();
+    if !patch.mods.is_empty() {
+        parts.push("mods");
+    }
+    if !patch.categories.is_empty() {
+        parts.push("categories");
+    }
+    for key in patch.fields.keys() {
+        let part = match key.as_str() {
+            "tags" | "tag_metadata" => "tags",
+            "profiles" => "profiles",
+            _ => "settings",
+        };
+        if !parts.contains(&part) {
+            parts.push(part);
+        }
+    }
+
+    format!("Edit {}", parts.join(", "))
+}
+
+fn patch_list(list: &mut Value, changes: &[(i64, Option<Value>)], state: &[&str]) {
+    let Some(items) = list.as_array_mut() else {
+        return;
+    };
+
+    for (id, value) in changes {
+        let position = items.iter().position(|item| item.get("id").and_then(Value::as_i64) == Some(*id));
+
+        match (position, value) {
+            (Some(i), Some(value)) => {
+                // Keep what the mod folder and the UI say over what was saved
+                let mut value = value.clone();
+                for key in state {
+                    if let (Some(object), Some(current)) = (value.as_object_mut(), items[i].get(*key)) {
+                        object.insert(key.to_string(), current.clone());
+                    }
+                }
+                items[i] = value;
+            }
+            (Some(i), None) => {
+                items.remove(i);
+            }
+            (None, Some(value)) => items.push(value.clone()),
+            (None, None) => {}
+        }
+    }
+}
+
+fn apply_patch(game_id: i64, patch: &DbPatch) -> Result<(), String> {
+    let db = load_game_db(game_id)?;
+    let mut value = serde_json::to_value(db).map_err(|e| e.to_string())?;
+
+    if let Some(object) = value.as_object_mut() {
+        for (key, field) in &patch.fields {
+            object.insert(key.clone(), field.clone());
+        }
+        if let Some(mods) = object.get_mut("mods") {
+            patch_list(mods, &patch.mods, MOD_STATE);
+        }
+        if let Some(categories) = object.get_mut("categories") {
+            patch_list(categories, &patch.categories, CATEGORY_STATE);
+        }
+    }
+
+    let db: Database = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to apply change: {}", e))?;
+    write_game_db(game_id, db)
+}
+
+// ============ FILE OPERATIONS ============
+
+// Sets each mod to its state, in order, putting back the ones already
+// changed if one fails.
+fn set_enabled(game_id: i64, targets: &[(String, bool)]) -> Result<(), String> {
+    let mut db = load_game_db(game_id)?;
+    let strategy = strategy::resolve(&db.mod_strategy)?;
+
+    for (i, (name, enable)) in targets.iter().enumerate() {
+        paths::check_name(name)?;
+
+        if let Err(e) = apply_toggle(strategy, &db.mod_context(name), *enable) {
+            for (done, was) in targets[..i].iter().rev() {
+                let _ = apply_toggle(strategy, &db.mod_context(done), !was);
+            }
+            return Err(format!("Failed to {} {}: {}", if *enable { "enable" } else { "disable" }, name, e));
+        }
+    }
+
+    for (name, enable) in targets {
+        if let Some(m) = db.mods.iter_mut().find(|m| &m.name == name) {
+            m.enabled = *enable;
+        }
+    }
+    write_game_db(game_id, db)
+}
+
+// Library mods are linked into the mod folder under their name, so one kept
+// outside the mod folder has to be unlinked before its folder moves.
+fn unlink(db: &Database, strategy: &dyn ModStrategy, root: &Path, name: &str) -> Result<bool, String> {
+    if root == Path::new(&db.root_folder) || !strategy.is_enabled(&db.mod_context(name)) {
+        return Ok(false);
+    }
+
+    strategy.disable(&db.mod_context(name))?;
+    Ok(true)
+}
+
+/// Renames the mod folder `from` in `root` to `to` and renames its record to
+/// match. A library mod that is linked into the mod folder is unlinked
+/// first and linked again under its new name.
+pub fn rename_entry(game_id: i64, root: &str, from: &str, to: &str) -> Result<(), String> {
+    let mut db = load_game_db(game_id)?;
+    let from_path = paths::game_entry_path(&db, root, from)?;
+    let to_path = paths::game_entry_path(&db, root, to)?;
+    let strategy = strategy::resolve(&db.mod_strategy)?;
+
+    if !from_path.exists() {
+        return Err(format!("Mod folder does not exist: {}", from_path.display()));
+    }
+    if to_path.exists() {
+        return Err(format!("A mod with name '{}' already exists", to));
+    }
+
+    let relink = unlink(&db, strategy, Path::new(root), from)?;
+
+    if let Err(e) = fs::rename(&from_path, &to_path) {
+        if relink {
+            let _ = strategy.enable(&db.mod_context(from));
+        }
+        return Err(format!("Failed to rename mod folder: {}", e));
+    }
+
+    if relink {
+        strategy.enable(&db.mod_context(to))?;
+    }
+
+    if let Some(m) = db.mods.iter_mut().find(|m| m.name == from) {
+        m.name = to.to_string();
+    }
+    write_game_db(game_id, db)
+}
+
+/// Adds the record of a mod just moved into a game's mod folder, unless the
+/// game already has a mod of that name.
+pub fn add_record(game_id: i64, record: Mod) -> Result<(), String> {
+    let mut db = load_game_db(game_id)?;
+    if db.mods.iter().any(|m| m.name == record.name) {
+        return Ok(());
+    }
+
+    db.mods.push(record);
+    write_game_db(game_id, db)
+}
+
+fn move_folder(from: &str, to: &str) -> Result<(), String> {
+    let (from, to) = (Path::new(from), Path::new(to));
+
+    if !from.exists() {
+        return Err(format!("Folder does not exist: {}", from.display()));
+    }
+    if to.exists() {
+        return Err(format!("Destination already exists: {}", to.display()));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create destination directory: {}", e))?;
+    }
+
+    transfer::move_dir(from, to, &mut |_| Ok(()))
+}
+
+/// Moves mod `name` of a game from `root` into the recycle bin along with
+/// its record, which is dropped from the database. A library mod is
+/// unlinked from the mod folder first.
+pub fn trash_mod(game_id: i64, root: &str, name: &str) -> Result<DeletedMod, String> {
+    let mut db = load_game_db(game_id)?;
+    let mod_path = paths::game_mod_path(&db, root, name)?;
+
+    if !mod_path.exists() {
+        return Err(format!("Mod folder does not exist: {}", mod_path.display()));
+    }
+
+    let strategy = strategy::resolve(&db.mod_strategy)?;
+    unlink(&db, strategy, Path::new(root), name)?;
+
+    let record = db.mods.iter().find(|m| m.name == name).cloned();
+    let entry = trash::move_to_trash(&mod_path, name, Some(game_id), record)?;
+
+    db.mods.retain(|m| m.name != name);
+    write_game_db(game_id, db)?;
+
+    Ok(DeletedMod {
+        trash_id: entry.id,
+        root: root.to_string(),
+        name: name.to_string(),
+    })
+}
+
+fn undo_change(game_id: i64, change: &mut Change) -> Result<(), String> {
+    match change {
+        Change::Toggle { mods } => {
+            let targets: Vec<(String, bool)> = mods.iter().rev().map(|m| (m.name.clone(), !m.enabled)).collect();
+            set_enabled(game_id, &targets)
+        }
+        Change::Rename { root, old_name, new_name } => rename_entry(game_id, root, new_name, old_name),
+        Change::Move { source, destination, record } => {
+            let mut db = load_game_db(game_id)?;
+            let strategy = strategy::resolve(&db.mod_strategy)?;
+            let moved = Path::new(destination.as_str());
+            if let (Some(root), Some(name)) = (moved.parent(), moved.file_name().and_then(|name| name.to_str())) {
+                unlink(&db, strategy, root, name)?;
+            }
+            move_folder(destination, source)?;
+
+            match record {
+                Some(record) => {
+                    db.mods.retain(|m| m.id != record.id);
+                    write_game_db(game_id, db)
+                }
+                None => Ok(()),
+            }
+        }
+        Change::Delete { mods } => {
+            for deleted in mods.iter().rev() {
+                trash::restore(&deleted.trash_id)?;
+            }
+            Ok(())
+        }
+        Change::Edit { before, .. } => apply_patch(game_id, before),
+    }
+}
+
+fn redo_change(game_id: i64, change: &mut Change) -> Result<(), String> {
+    match change {
+        Change::Toggle { mods } => {
+            let targets: Vec<(String, bool)> = mods.iter().map(|m| (m.name.clone(), m.enabled)).collect();
+            set_enabled(game_id, &targets)
+        }
+        Change::Rename { root, old_name, new_name } => rename_entry(game_id, root, old_name, new_name),
+        Change::Move { source, destination, record } => {
+            move_folder(source, destination)?;
+
+            match record {
+                Some(record) => add_record(game_id, record.clone()),
+                None => Ok(()),
+            }
+        }
+        Change::Delete { mods } => {
+            // Each delete lands in a new recycle bin entry
+            for deleted in mods.iter_mut() {
+                *deleted = trash_mod(game_id, &deleted.root, &deleted.name)?;
+            }
+            Ok(())
+        }
+        Change::Edit { after, .. } => apply_patch(game_id, after),
+    }
+}
+
+#[tauri::command]
+pub fn history(game_id: i64) -> HistoryInfo {
+    let history = load(game_id);
+
+    HistoryInfo {
+        undo: history.undo.iter().rev().map(HistoryEntry::from).collect(),
+        redo: history.redo.iter().rev().map(HistoryEntry::from).collect(),
+    }
+}
+
+/// Reverses the most recent operation of a game. If that fails, the
+/// operation stays at the top of the history.
+#[tauri::command]
+pub fn undo(game_id: i64, watcher: tauri::State<'_, ModWatcher>) -> Result<HistoryEntry, String> {
+    let _pause = watcher.pause();
+    undo_last(game_id)
+}
+
+fn undo_last(game_id: i64) -> Result<HistoryEntry, String> {
+    let mut history = load(game_id);
+    let mut operation = history.undo.pop()
+        .ok_or_else(|| "Nothing to undo".to_string())?;
+
+    undo_change(game_id, &mut operation.change)
+        .map_err(|e| format!("Failed to undo {}: {}", operation.label, e))?;
+
+    let entry = HistoryEntry::from(&operation);
+    history.redo.push(operation);
+    save(game_id, &history)?;
+
+    Ok(entry)
+}
+
+/// Performs the most recently undone operation again.
+#[tauri::command]
+pub fn redo(game_id: i64, watcher: tauri::State<'_, ModWatcher>) -> Result<HistoryEntry, String> {
+    let _pause = watcher.pause();
+    redo_last(game_id)
+}
+
+fn redo_last(game_id: i64) -> Result<HistoryEntry, String> {
+    let mut history = load(game_id);
+    let mut operation = history.redo.pop()
+        .ok_or_else(|| "Nothing to redo".to_string())?;
+
+    redo_change(game_id, &mut operation.change)
+        .map_err(|e| format!("Failed to redo {}: {}", operation.label, e))?;
+
+    let entry = HistoryEntry::from(&operation);
+    history.undo.push(operation);
+    save(game_id, &history)?;
+
+    Ok(entry)
+}
+
+/// Forgets every recorded operation, for when one can no longer be undone
+/// and blocks the ones before it.
+#[tauri::command]
+pub fn clear_history(game_id: i64) -> Result<(), String> {
+    save(game_id, &History { next_id: load(game_id).next_id, ..Default::default() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestDataDir;
+    use serde_json::json;
+
+    const GAME: i64 = 1;
+
+    fn record_json(id: i64, name: &str, enabled: bool) -> Value {
+        json!({
+            "id": id,
+            "name": name,
+            "category_id": 0,
+            "enabled": enabled,
+            "notes": "",
+            "preview": null,
+            "tags": [],
+            "presets": [],
+        })
+    }
+
+    // A generic_rename game in `data` whose mods are enabled or disabled as
+    // given, saved as game `GAME`.
+    fn game(data: &TestDataDir, mods: &[(&str, bool)]) -> Database {
+        let root = data.0.join("Mods");
+        fs::create_dir_all(root.join("_Disabled")).unwrap();
+
+        let records: Vec<Value> = mods.iter()
+            .enumerate()
+            .map(|(i, (name, enabled))| {
+                let folder = if *enabled { root.join(name) } else { root.join("_Disabled").join(name) };
+                fs::create_dir_all(&folder).unwrap();
+                fs::write(folder.join("mod.ini"), "[Constants]\n").unwrap();
+                record_json(i as i64 + 1, name, *enabled)
+            })
+            .collect();
+
+        let db: Database = serde_json::from_value(json!({
+            "root_folder": root,
+            "disabled_folder": "_Disabled",
+            "mod_strategy": "generic_rename",
+            "categories": [{ "id": 1, "name": "Root", "parent_id": null, "expanded": true }],
+            "mods": records,
+            "tags": [],
+            "tag_metadata": [],
+            "profiles": [],
+        })).unwrap();
+        write_game_db(GAME, serde_json::from_value(serde_json::to_value(&db).unwrap()).unwrap()).unwrap();
+        db
+    }
+
+    // Saves `db` the way the UI does, recording the edit.
+    fn save_edit(db: &Value) {
+        let before = serde_json::to_value(load_game_db(GAME).unwrap()).unwrap();
+        write_game_db(GAME, serde_json::from_value(db.clone()).unwrap()).unwrap();
+        record_edit(GAME, &before, db).unwrap();
+    }
+
+    fn notes(name: &str) -> String {
+        let db = load_game_db(GAME).unwrap();
+        db.mods.iter().find(|m| m.name == name).unwrap().notes.clone()
+    }
+
+    fn labels() -> (Vec<String>, Vec<String>) {
+        let info = history(GAME);
+        (
+            info.undo.into_iter().map(|entry| entry.label).collect(),
+            info.redo.into_iter().map(|entry| entry.label).collect(),
+        )
+    }
+
+    #[test]
+    fn diff_leaves_out_folder_and_ui_state() {
+        let before = json!({
+            "root_folder": "C:\\Mods",
+            "mods": [record_json(1, "A", true), record_json(2, "B", false)],
+            "categories": [{ "id": 1, "name": "Root", "parent_id": null, "expanded": true }],
+        });
+
+        let mut toggled = before.clone();
+        toggled["mods"][0]["enabled"] = json!(false);
+        toggled["categories"][0]["expanded"] = json!(false);
+        let (_, after) = diff(&before, &toggled);
+        assert!(after.is_empty());
+
+        let mut edited = toggled.clone();
+        edited["mods"][1]["notes"] = json!("favourite");
+        edited["root_folder"] = json!("D:\\Mods");
+        let (old, new) = diff(&before, &edited);
+        assert_eq!(new.mods.len(), 1);
+        assert_eq!(old.mods[0].0, 2);
+        assert_eq!(new.fields["root_folder"], json!("D:\\Mods"));
+        assert_eq!(old.fields["root_folder"], json!("C:\\Mods"));
+        assert_eq!(describe(&new), "Edit mods, settings");
+        assert_eq!(
+            edited_paths(&old, &new),
+            ["fields/root_folder", "mods/2/notes"]
+        );
+    }
+
+    fn edited_paths(before: &DbPatch, after: &DbPatch) -> Vec<String> {
+        edited(before, after).into_iter().collect()
+    }
+
+    #[test]
+    fn diff_records_added_and_removed_mods() {
+        let before = json!({ "mods": [record_json(1, "A", true)] });
+        let after = json!({ "mods": [record_json(2, "B", true)] });
+
+        let (old, new) = diff(&before, &after);
+
+        assert_eq!(old.mods, [(1, Some(record_json(1, "A", true))), (2, None)]);
+        assert_eq!(new.mods, [(1, None), (2, Some(record_json(2, "B", true)))]);
+        assert_eq!(edited_paths(&old, &new), ["mods/1", "mods/2"]);
+    }
+
+    #[test]
+    fn merges_successive_edits_of_the_same_field() {
+        let data = TestDataDir::new("history-merge");
+        let original = serde_json::to_value(game(&data, &[("A", true)])).unwrap();
+
+        let mut db = original.clone();
+        for text in ["f", "fa", "fav"] {
+            db["mods"][0]["notes"] = json!(text);
+            save_edit(&db);
+        }
+        assert_eq!(labels().0, ["Edit mods"]);
+
+        db["tags"] = json!(["Outfit"]);
+        save_edit(&db);
+        assert_eq!(labels().0, ["Edit tags", "Edit mods"]);
+
+        undo_last(GAME).unwrap();
+        undo_last(GAME).unwrap();
+        assert_eq!(notes("A"), "");
+        assert_eq!(labels().1, ["Edit mods", "Edit tags"]);
+
+        // An edit made after an undo starts a new entry and drops the redo list
+        db = original.clone();
+        db["mods"][0]["notes"] = json!("x");
+        save_edit(&db);
+        assert_eq!(labels(), (vec!["Edit mods".to_string()], vec![]));
+
+        // Erasing what was typed leaves nothing to undo
+        save_edit(&original);
+        assert_eq!(labels(), (vec![], vec![]));
+    }
+
+    #[test]
+    fn undoes_and_redoes_an_edit() {
+        let data = TestDataDir::new("history-edit");
+        let mut db = serde_json::to_value(game(&data, &[("A", true), ("B", false)])).unwrap();
+
+        db["mods"][1]["notes"] = json!("keep");
+        save_edit(&db);
+
+        let entry = undo_last(GAME).unwrap();
+        assert_eq!(entry.label, "Edit mods");
+        assert_eq!(notes("B"), "");
+
+        redo_last(GAME).unwrap();
+        assert_eq!(notes("B"), "keep");
+        assert!(redo_last(GAME).is_err());
+    }
+
+    #[test]
+    fn undoes_and_redoes_a_toggle() {
+        let data = TestDataDir::new("history-toggle");
+        let db = game(&data, &[("A", true)]);
+        let strategy = strategy::resolve(&db.mod_strategy).unwrap();
+
+        apply_toggle(strategy, &db.mod_context("A"), false).unwrap();
+        let change = ModChange { name: "A".to_string(), enabled: false, exclusive_tag: None, caused_by: None };
+        record_toggle(GAME, &["A".to_string()], false, &[change]).unwrap();
+
+        undo_last(GAME).unwrap();
+        assert!(data.0.join("Mods/A").exists());
+        assert!(load_game_db(GAME).unwrap().mods[0].enabled);
+
+        redo_last(GAME).unwrap();
+        assert!(data.0.join("Mods/_Disabled/A").exists());
+        assert!(!load_game_db(GAME).unwrap().mods[0].enabled);
+    }
+
+    #[test]
+    fn undoes_a_move_together_with_its_record() {
+        let data = TestDataDir::new("history-move");
+        game(&data, &[]);
+        let source = data.0.join("Downloads/New");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("mod.ini"), "[Constants]\n").unwrap();
+        let destination = data.0.join("Mods/New");
+
+        // What move_mod does for an install
+        transfer::move_dir(&source, &destination, &mut |_| Ok(())).unwrap();
+        let added: Mod = serde_json::from_value(record_json(7, "New", true)).unwrap();
+        add_record(GAME, added.clone()).unwrap();
+        record(GAME, "Add New".to_string(), Change::Move {
+            source: source.to_string_lossy().to_string(),
+            destination: destination.to_string_lossy().to_string(),
+            record: Some(added),
+        }).unwrap();
+
+        undo_last(GAME).unwrap();
+        assert!(source.join("mod.ini").exists());
+        assert!(!destination.exists());
+        assert!(load_game_db(GAME).unwrap().mods.is_empty());
+
+        redo_last(GAME).unwrap();
+        assert!(destination.join("mod.ini").exists());
+        assert!(!source.exists());
+        let names: Vec<String> = load_game_db(GAME).unwrap().mods.into_iter().map(|m| m.name).collect();
+        assert_eq!(names, ["New"]);
+    }
+
+    #[test]
+    fn failed_undo_stays_in_the_history() {
+        let data = TestDataDir::new("history-failed");
+        game(&data, &[]);
+        record(GAME, "Move Gone".to_string(), Change::Move {
+            source: data.0.join("Downloads/Gone").to_string_lossy().to_string(),
+            destination: data.0.join("Mods/Gone").to_string_lossy().to_string(),
+            record: None,
+        }).unwrap();
+
+        let error = undo_last(GAME).unwrap_err();
+
+        assert!(error.starts_with("Failed to undo Move Gone"), "{}", error);
+        assert_eq!(labels(), (vec!["Move Gone".to_string()], vec![]));
+    }
+}
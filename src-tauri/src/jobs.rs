@@ -0,0 +1,249 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+
+/// Error returned by an operation stopped through `cancel_job`.
+pub const CANCELLED: &str = "Cancelled";
+
+// Finished jobs kept around so a reloaded window can still see how they ended
+const MAX_FINISHED: usize = 20;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct JobProgress {
+    pub bytes_done: u64,
+    // Zero when the total isn't known up front, as for compressed archives
+    pub bytes_total: u64,
+    pub files_done: usize,
+    pub files_total: usize,
+    pub current_path: String,
+    pub bytes_per_second: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct JobInfo {
+    pub id: u64,
+    pub kind: String,
+    pub label: String,
+    pub state: JobState,
+    pub progress: JobProgress,
+    pub error: Option<String>,
+    pub started_at: u64,
+}
+
+struct Job {
+    info: JobInfo,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Tracks long-running file operations so they can report progress, be
+/// listed after a window reload and be cancelled.
+#[derive(Default)]
+pub struct JobManager {
+    jobs: Mutex<Vec<Job>>,
+    next_id: AtomicU64,
+}
+
+impl JobManager {
+    pub fn start(&self, window: &tauri::Window, kind: &str, label: &str) -> JobHandle<'_> {
+        let (info, cancelled) = self.add(kind, label);
+        let _ = window.emit("job-progress", info.clone());
+
+        JobHandle { manager: self, window: window.clone(), id: info.id, cancelled }
+    }
+
+    fn add(&self, kind: &str, label: &str) -> (JobInfo, Arc<AtomicBool>) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let info = JobInfo {
+            id,
+            kind: kind.to_string(),
+            label: label.to_string(),
+            state: JobState::Running,
+            progress: JobProgress::default(),
+            error: None,
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+        };
+
+        self.jobs.lock().unwrap().push(Job { info: info.clone(), cancelled: cancelled.clone() });
+        (info, cancelled)
+    }
+
+    fn update(&self, id: u64, change: impl FnOnce(&mut JobInfo)) -> Option<JobInfo> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.iter_mut().find(|job| job.info.id == id)?;
+        change(&mut job.info);
+        Some(job.info.clone())
+    }
+
+    // Records how the job ended and drops the oldest finished jobs
+    fn end(&self, id: u64, state: JobState, error: Option<String>) -> Option<JobInfo> {
+        let info = self.update(id, |info| {
+            info.state = state;
+            info.error = error;
+        });
+        self.prune();
+        info
+    }
+
+    fn prune(&self) {
+        let mut jobs = self.jobs.lock().unwrap();
+        let finished = jobs.iter().filter(|job| job.info.state != JobState::Running).count();
+
+        let mut excess = finished.saturating_sub(MAX_FINISHED);
+        jobs.retain(|job| {
+            if excess > 0 && job.info.state != JobState::Running {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    fn cancel(&self, id: u64) -> Result<(), String> {
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs.iter()
+            .find(|job| job.info.id == id)
+            .ok_or_else(|| format!("Job not found: {}", id))?;
+
+        if job.info.state != JobState::Running {
+            return Err(format!("Job {} has already finished", id));
+        }
+
+        job.cancelled.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn list(&self) -> Vec<JobInfo> {
+        self.jobs.lock().unwrap().iter().map(|job| job.info.clone()).collect()
+    }
+}
+
+// The state a job ends in. An operation that fails after being cancelled
+// was stopped rather than broken, so it has no error to show.
+fn outcome<T>(result: &Result<T, String>, cancelled: bool) -> (JobState, Option<String>) {
+    match result {
+        Ok(_) => (JobState::Completed, None),
+        Err(_) if cancelled => (JobState::Cancelled, None),
+        Err(e) => (JobState::Failed, Some(e.clone())),
+    }
+}
+
+/// A running job. Operations report progress through it and stop at the
+/// next `progress` call once the job is cancelled.
+pub struct JobHandle<'a> {
+    manager: &'a JobManager,
+    window: tauri::Window,
+    id: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl JobHandle<'_> {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Records and emits new progress, failing with `CANCELLED` once the job
+    /// has been cancelled so callers can bail out with `?`.
+    pub fn progress(&self, progress: &JobProgress) -> Result<(), String> {
+        if let Some(info) = self.manager.update(self.id, |info| info.progress = progress.clone()) {
+            let _ = self.window.emit("job-progress", info);
+        }
+
+        if self.is_cancelled() {
+            return Err(CANCELLED.to_string());
+        }
+        Ok(())
+    }
+
+    /// Marks the job as finished with the outcome of the operation, which is
+    /// passed through unchanged.
+    pub fn finish<T>(self, result: Result<T, String>) -> Result<T, String> {
+        let (state, error) = outcome(&result, self.is_cancelled());
+
+        if let Some(info) = self.manager.end(self.id, state, error) {
+            let _ = self.window.emit("job-progress", info);
+        }
+
+        result
+    }
+}
+
+#[tauri::command]
+pub fn cancel_job(id: u64, jobs: tauri::State<'_, JobManager>) -> Result<(), String> {
+    jobs.cancel(id)
+}
+
+/// Running jobs and the most recently finished ones, oldest first.
+#[tauri::command]
+pub fn list_jobs(jobs: tauri::State<'_, JobManager>) -> Vec<JobInfo> {
+    jobs.list()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn states(jobs: &JobManager) -> Vec<(u64, JobState)> {
+        jobs.list().into_iter().map(|job| (job.id, job.state)).collect()
+    }
+
+    #[test]
+    fn cancels_running_jobs_only() {
+        let jobs = JobManager::default();
+        let (copy, cancelled) = jobs.add("copy", "Outfit");
+        let (extract, _) = jobs.add("extract", "Hair");
+
+        jobs.cancel(copy.id).unwrap();
+        assert!(cancelled.load(Ordering::SeqCst));
+
+        let result: Result<(), String> = Err(CANCELLED.to_string());
+        let (state, error) = outcome(&result, true);
+        jobs.end(copy.id, state, error);
+        jobs.end(extract.id, JobState::Completed, None);
+
+        assert_eq!(states(&jobs), [(copy.id, JobState::Cancelled), (extract.id, JobState::Completed)]);
+        assert_eq!(jobs.list()[0].error, None);
+        assert_eq!(jobs.cancel(copy.id), Err(format!("Job {} has already finished", copy.id)));
+        assert_eq!(jobs.cancel(99), Err("Job not found: 99".to_string()));
+    }
+
+    #[test]
+    fn keeps_the_error_of_a_failed_job() {
+        let result: Result<(), String> = Err("Disk full".to_string());
+
+        assert_eq!(outcome(&result, false), (JobState::Failed, Some("Disk full".to_string())));
+        assert_eq!(outcome(&Ok::<_, String>(()), true), (JobState::Completed, None));
+    }
+
+    #[test]
+    fn evicts_the_oldest_finished_jobs() {
+        let jobs = JobManager::default();
+        let (running, _) = jobs.add("copy", "Still going");
+        let finished: Vec<u64> = (0..MAX_FINISHED + 2)
+            .map(|i| {
+                let (info, _) = jobs.add("extract", &format!("Mod {}", i));
+                jobs.end(info.id, JobState::Completed, None);
+                info.id
+            })
+            .collect();
+
+        let ids: Vec<u64> = jobs.list().into_iter().map(|job| job.id).collect();
+        assert_eq!(ids.len(), MAX_FINISHED + 1);
+        assert_eq!(ids[0], running.id);
+        assert_eq!(ids[1..], finished[2..]);
+    }
+}
@@ -0,0 +1,509 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::exclusive::{self, ModChange, ToggleReport};
+use crate::jobs::{JobHandle, JobManager, JobProgress};
+use crate::strategy::{self, ModContext, ModStrategy};
+use crate::transfer::Report;
+use crate::watcher::ModWatcher;
+use crate::{apply_toggle, get_data_dir, history, load_game_db, paths, storage, write_game_db, Database};
+
+/// One mod toggled by a bulk toggle, including mods disabled by a mutually
+/// exclusive tag.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JournalStep {
+    pub name: String,
+    pub enable: bool,
+}
+
+/// A bulk toggle in progress. It is written to disk before each toggle, so a
+/// run stopped by a crash can be finished or undone on the next start.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Journal {
+    pub id: String,
+    pub game_id: Option<i64>,
+    pub root: String,
+    pub disabled_folder: String,
+    pub library_folder: String,
+    pub strategy: String,
+    pub enable: bool,
+    pub mods: Vec<String>,
+    // Index into `mods` of the first mod not fully toggled yet
+    pub next: usize,
+    pub steps: Vec<JournalStep>,
+    pub started_at: u64,
+    // Folder the journal is kept in
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+fn journal_dir() -> PathBuf {
+    get_data_dir().join("journals")
+}
+
+fn journal_path(id: &str) -> PathBuf {
+    journal_dir().join(format!("{}.json", id))
+}
+
+// Strategies leave a mod alone when it is already in the requested state, so
+// replaying a toggle that was cut short finishes it without the checks
+// `apply_toggle` makes for a fresh one.
+fn replay(strategy: &dyn ModStrategy, ctx: &ModContext, enable: bool) -> Result<(), String> {
+    if enable {
+        strategy.enable(ctx)
+    } else {
+        strategy.disable(ctx)
+    }
+}
+
+impl Journal {
+    pub fn begin(
+        game_id: Option<i64>,
+        root: &str,
+        disabled_folder: &str,
+        library_folder: &str,
+        strategy: &str,
+        mods: Vec<String>,
+        enable: bool
+    ) -> Result<Journal, String> {
+        strategy::resolve(strategy)?;
+
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        let journal = Journal {
+            id: String::new(),
+            game_id,
+            root: root.to_string(),
+            disabled_folder: disabled_folder.to_string(),
+            library_folder: library_folder.to_string(),
+            strategy: strategy.to_string(),
+            enable,
+            mods,
+            next: 0,
+            steps: vec![],
+            started_at,
+            dir: journal_dir(),
+        };
+        journal.start()
+    }
+
+    // Writes a new journal to its folder under an id not taken yet.
+    fn start(mut self) -> Result<Journal, String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create journal folder: {}", e))?;
+
+        self.id = self.started_at.to_string();
+        let mut suffix = 1;
+        while self.path().exists() {
+            self.id = format!("{}-{}", self.started_at, suffix);
+            suffix += 1;
+        }
+
+        self.save()?;
+        Ok(self)
+    }
+
+    fn load(id: &str) -> Result<Journal, String> {
+        paths::check_name(id)?;
+        storage::read_json(&journal_path(id))
+            .map(|journal| Journal { dir: journal_dir(), ..journal })
+            .map_err(|e| format!("Journal not found: {} ({})", id, e))
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.join(format!("{}.json", self.id))
+    }
+
+    fn save(&self) -> Result<(), String> {
+        storage::write_json(&self.path(), self)
+    }
+
+    fn finish(self) {
+        let _ = storage::remove_json(&self.path());
+    }
+
+    fn context<'a>(&'a self, name: &'a str) -> ModContext<'a> {
+        ModContext {
+            root: Path::new(&self.root),
+            name,
+            disabled_folder: &self.disabled_folder,
+            library_folder: &self.library_folder,
+        }
+    }
+
+    /// Records the toggle of `name` and then performs it. A mod already in the
+    /// requested state is left out of the journal, so a rollback can't flip
+    /// it; returns whether the mod was toggled.
+    pub fn toggle(&mut self, strategy: &dyn ModStrategy, name: &str, enable: bool) -> Result<bool, String> {
+        if strategy.is_enabled(&self.context(name)) == enable {
+            return Ok(false);
+        }
+
+        self.steps.push(JournalStep { name: name.to_string(), enable });
+        self.save()?;
+
+        // Folder moves and ini renames undo their own partial work when they
+        // fail, so a failed toggle has nothing to roll back
+        if let Err(e) = apply_toggle(strategy, &self.context(name), enable) {
+            self.steps.pop();
+            let _ = self.save();
+            return Err(e);
+        }
+
+        Ok(true)
+    }
+
+    // Toggles the remaining mods, stopping at the first error or cancellation.
+    fn run(
+        &mut self,
+        mut db: Option<&mut Database>,
+        strategy: &dyn ModStrategy,
+        changes: &mut Vec<ModChange>,
+        report: Report
+    ) -> Result<(), String> {
+        let mut progress = JobProgress {
+            files_done: self.next,
+            files_total: self.mods.len(),
+            ..Default::default()
+        };
+
+        while self.next < self.mods.len() {
+            let name = self.mods[self.next].clone();
+            progress.current_path = name.clone();
+            report(&progress)?;
+
+            let result = match db.as_deref_mut() {
+                Some(db) => paths::check_name(&name)
+                    .and_then(|_| exclusive::toggle(db, strategy, &name, self.enable, changes, Some(self))),
+                None => paths::check_context(&self.context(&name))
+                    .and_then(|_| self.toggle(strategy, &name, self.enable))
+                    .map(|toggled| if toggled {
+                        changes.push(ModChange {
+                            name: name.clone(),
+                            enabled: self.enable,
+                            exclusive_tag: None,
+                            caused_by: None,
+                        });
+                    }),
+            };
+            result.map_err(|e| format!("{}: {}", name, e))?;
+
+            self.next += 1;
+            self.save()?;
+            progress.files_done += 1;
+        }
+
+        Ok(())
+    }
+
+    // Brings the journal's toggles back in, for a run interrupted part way.
+    fn redo(&self, strategy: &dyn ModStrategy, changes: &mut Vec<ModChange>) -> Result<(), String> {
+        for step in &self.steps {
+            replay(strategy, &self.context(&step.name), step.enable)
+                .map_err(|e| format!("{}: {}", step.name, e))?;
+            changes.push(ModChange {
+                name: step.name.clone(),
+                enabled: step.enable,
+                exclusive_tag: None,
+                caused_by: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Reverses every recorded toggle, newest first. Returns the mods that
+    /// could not be restored.
+    fn rollback(&self, strategy: &dyn ModStrategy) -> Vec<String> {
+        self.steps.iter()
+            .rev()
+            .filter_map(|step| {
+                replay(strategy, &self.context(&step.name), !step.enable)
+                    .err()
+                    .map(|e| format!("Failed to restore {}: {}", step.name, e))
+            })
+            .collect()
+    }
+
+    // Records in `db` whether each mod the journal touched is enabled on disk.
+    fn sync(&self, db: &mut Database, strategy: &dyn ModStrategy) {
+        for step in &self.steps {
+            let enabled = strategy.is_enabled(&self.context(&step.name));
+            if let Some(m) = db.mods.iter_mut().find(|m| m.name == step.name) {
+                m.enabled = enabled;
+            }
+        }
+    }
+}
+
+/// Toggles every mod of `journal` and saves the game's database. If any
+/// toggle fails or the job is cancelled, all of them are rolled back instead
+/// and the report lists the error and no changes. A journal whose rollback
+/// also failed is kept so it can be reverted again later.
+pub fn complete(mut journal: Journal, mut db: Option<Database>, job: JobHandle) -> Result<ToggleReport, String> {
+    let result = apply(&mut journal, db.as_mut(), &mut |progress| job.progress(progress))
+        .and_then(|outcome| conclude(journal, db, outcome));
+
+    let outcome = match &result {
+        Ok(report) if report.errors.is_empty() => Ok(()),
+        Ok(report) => Err(report.errors.join("\n")),
+        Err(e) => Err(e.clone()),
+    };
+    let _ = job.finish(outcome);
+
+    result
+}
+
+// Toggles the mods of `journal`, rolling them all back on an error, and
+// records in `db` where they ended up.
+fn apply(journal: &mut Journal, mut db: Option<&mut Database>, report: Report) -> Result<ToggleReport, String> {
+    let strategy = strategy::resolve(&journal.strategy)?;
+    let mut outcome = ToggleReport::default();
+
+    let result = journal.redo(strategy, &mut outcome.changes).and_then(|_| {
+        if let Some(db) = db.as_mut() {
+            journal.sync(db, strategy);
+        }
+        journal.run(db.as_deref_mut(), strategy, &mut outcome.changes, report)
+    });
+
+    if let Err(e) = result {
+        outcome.changes.clear();
+        outcome.errors.push(e);
+
+        outcome.errors.extend(journal.rollback(strategy));
+
+        if let Some(db) = db.as_mut() {
+            journal.sync(db, strategy);
+        }
+    }
+
+    Ok(outcome)
+}
+
+// Saves the database and history after `apply`. The mods are where the
+// report says either way, so the journal is dropped even if saving fails;
+// only a failed rollback, which adds errors after the first, keeps it.
+fn conclude(journal: Journal, db: Option<Database>, outcome: ToggleReport) -> Result<ToggleReport, String> {
+    let saved = match (journal.game_id, db) {
+        (Some(game_id), Some(db)) => write_game_db(game_id, db)
+            .and_then(|_| history::record_toggle(game_id, &journal.mods, journal.enable, &outcome.changes)),
+        _ => Ok(()),
+    };
+
+    if outcome.errors.len() <= 1 {
+        journal.finish();
+    }
+    saved.map(|_| outcome)
+}
+
+/// Bulk toggles left unfinished by a crash or a closed window, oldest first.
+#[tauri::command]
+pub fn list_journals() -> Vec<Journal> {
+    let entries = match fs::read_dir(journal_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut journals: Vec<Journal> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| storage::read_json(&entry.path()).ok())
+        .collect();
+
+    journals.sort_by_key(|journal: &Journal| journal.started_at);
+    journals
+}
+
+/// Finishes an interrupted bulk toggle: toggles it made are redone and the
+/// mods it had not reached yet are toggled.
+#[tauri::command]
+pub async fn resume_journal(
+    id: String,
+    window: tauri::Window,
+    jobs: tauri::State<'_, JobManager>,
+    watcher: tauri::State<'_, ModWatcher>
+) -> Result<ToggleReport, String> {
+    let _pause = watcher.pause();
+    let journal = Journal::load(&id)?;
+    let db = match journal.game_id {
+        Some(game_id) => Some(load_game_db(game_id)?),
+        None => None,
+    };
+
+    let job = jobs.start(&window, "toggle", "Resume toggle");
+    complete(journal, db, job)
+}
+
+/// Undoes every toggle an interrupted bulk toggle made. Returns the mods
+/// that could not be restored; the journal is kept while there are any.
+#[tauri::command]
+pub fn revert_journal(id: String, watcher: tauri::State<'_, ModWatcher>) -> Result<Vec<String>, String> {
+    let _pause = watcher.pause();
+    let journal = Journal::load(&id)?;
+    let strategy = strategy::resolve(&journal.strategy)?;
+
+    let failed = journal.rollback(strategy);
+
+    let saved = match journal.game_id {
+        Some(game_id) => load_game_db(game_id).and_then(|mut db| {
+            journal.sync(&mut db, strategy);
+            write_game_db(game_id, db)
+        }),
+        None => Ok(()),
+    };
+
+    if failed.is_empty() {
+        journal.finish();
+    }
+    saved.map(|_| failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::CANCELLED;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("wwmm-journal-{}-{}", label, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    // A generic_rename game whose mods are enabled or disabled as given.
+    fn game(dir: &TempDir, mods: &[(&str, bool)]) -> Database {
+        let root = dir.0.join("Mods");
+        fs::create_dir_all(root.join("_Disabled")).unwrap();
+
+        let records: Vec<serde_json::Value> = mods.iter()
+            .enumerate()
+            .map(|(i, (name, enabled))| {
+                let folder = if *enabled { root.join(name) } else { root.join("_Disabled").join(name) };
+                fs::create_dir_all(&folder).unwrap();
+                fs::write(folder.join("mod.ini"), "[Constants]\n").unwrap();
+
+                serde_json::json!({
+                    "id": i + 1,
+                    "name": name,
+                    "category_id": 0,
+                    "enabled": enabled,
+                    "notes": "",
+                    "preview": null,
+                    "tags": [],
+                })
+            })
+            .collect();
+
+        serde_json::from_value(serde_json::json!({
+            "root_folder": root,
+            "disabled_folder": "_Disabled",
+            "mod_strategy": "generic_rename",
+            "categories": [],
+            "mods": records,
+            "tags": [],
+            "tag_metadata": [],
+            "profiles": [],
+        })).unwrap()
+    }
+
+    // Without a game id, finishing a run saves nothing outside `dir`.
+    fn begin(dir: &TempDir, db: &Database, mods: &[&str], enable: bool) -> Journal {
+        Journal {
+            id: String::new(),
+            game_id: None,
+            root: db.root_folder.clone(),
+            disabled_folder: db.disabled_folder.clone(),
+            library_folder: db.library_folder.clone(),
+            strategy: db.mod_strategy.clone(),
+            enable,
+            mods: mods.iter().map(|name| name.to_string()).collect(),
+            next: 0,
+            steps: vec![],
+            started_at: 1,
+            dir: dir.0.join("journals"),
+        }.start().unwrap()
+    }
+
+    fn enabled(dir: &TempDir, name: &str) -> bool {
+        dir.0.join("Mods").join(name).exists()
+    }
+
+    #[test]
+    fn completes_mixed_selection() {
+        let dir = TempDir::new("complete");
+        let mut db = game(&dir, &[("A", true), ("B", false), ("C", true)]);
+        let mut journal = begin(&dir, &db, &["A", "B", "C"], false);
+        let path = journal.path();
+
+        let report = apply(&mut journal, Some(&mut db), &mut |_| Ok(())).unwrap();
+        let report = conclude(journal, None, report).unwrap();
+
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        let changed: Vec<&str> = report.changes.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(changed, ["A", "C"]);
+        for name in ["A", "B", "C"] {
+            assert!(!enabled(&dir, name), "{} is still enabled", name);
+        }
+        assert!(db.mods.iter().all(|m| !m.enabled));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn rollback_leaves_mods_already_in_state() {
+        let dir = TempDir::new("rollback");
+        let mut db = game(&dir, &[("A", true), ("B", false), ("C", false)]);
+        let mut journal = begin(&dir, &db, &["A", "B", "C"], true);
+        let path = journal.path();
+
+        // Cancelled once A and B are done
+        let report = apply(&mut journal, Some(&mut db), &mut |progress| {
+            if progress.files_done == 2 {
+                Err(CANCELLED.to_string())
+            } else {
+                Ok(())
+            }
+        }).unwrap();
+        let report = conclude(journal, None, report).unwrap();
+
+        assert_eq!(report.errors, [CANCELLED]);
+        assert!(report.changes.is_empty());
+        assert!(enabled(&dir, "A"));
+        assert!(!enabled(&dir, "B"));
+        assert!(!enabled(&dir, "C"));
+        let states: Vec<bool> = db.mods.iter().map(|m| m.enabled).collect();
+        assert_eq!(states, [true, false, false]);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn keeps_journal_whose_rollback_failed() {
+        let dir = TempDir::new("unrestored");
+        let db = game(&dir, &[("A", false)]);
+        let journal = begin(&dir, &db, &["A"], true);
+        let path = journal.path();
+
+        let report = ToggleReport {
+            changes: vec![],
+            errors: vec!["A: Cancelled".to_string(), "Failed to restore A: in use".to_string()],
+        };
+        conclude(journal, None, report).unwrap();
+
+        assert!(path.exists());
+    }
+}
@@ -2,11 +2,38 @@
 
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::io;
 use std::path::{Path, PathBuf};
-use zip::ZipArchive;
 use tauri::Emitter;
 
+mod archive;
+mod backups;
+mod conflicts;
+mod datadir;
+mod exclusive;
+mod history;
+mod jobs;
+mod journal;
+mod migoto;
+mod paths;
+mod presets;
+mod profiles;
+mod scan;
+mod schema;
+mod storage;
+mod strategy;
+#[cfg(test)]
+mod test_support;
+mod transfer;
+mod trash;
+mod watcher;
+
+use exclusive::{ModChange, ToggleReport};
+use history::{Change, DeletedMod};
+use jobs::JobManager;
+use journal::Journal;
+use strategy::{ModContext, ModStrategy, StrategyInfo};
+use watcher::ModWatcher;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Category {
     pub id: i64,
@@ -21,6 +48,18 @@ pub struct TagMetadata {
     pub description: String,
     pub preview: Option<String>,
     pub mutually_exclusive: bool,
+    #[serde(default)]
+    pub exclusive_mode: ExclusiveMode,
+}
+
+/// What happens when a mod is enabled while another mod sharing one of its
+/// mutually exclusive tags is already enabled.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExclusiveMode {
+    #[default]
+    Refuse,
+    AutoDisable,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -32,17 +71,75 @@ pub struct Mod {
     pub notes: String,
     pub preview: Option<String>,
     pub tags: Vec<String>,
+    #[serde(default)]
+    pub presets: Vec<VariablePreset>,
+}
+
+/// Value of one persisted 3DMigoto `$variable` declared in a mod's ini file.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VariableValue {
+    pub file: String,
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VariablePreset {
+    pub name: String,
+    pub values: Vec<VariableValue>,
+}
+
+/// A named set of mods that should be enabled, all others disabled.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub mod_ids: Vec<i64>,
+    #[serde(default)]
+    pub presets: Vec<ProfilePreset>,
+}
+
+/// Variable preset of a mod that a profile restores after toggling.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProfilePreset {
+    pub mod_id: i64,
+    pub preset: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Database {
+    #[serde(default = "schema::current_version")]
+    pub schema_version: u32,
     pub root_folder: String,
     pub disabled_folder: String,
     pub mod_strategy: String,
+    #[serde(default)]
+    pub library_folder: String,
     pub categories: Vec<Category>,
     pub mods: Vec<Mod>,
     pub tags: Vec<String>,
     pub tag_metadata: Vec<TagMetadata>,
+    pub profiles: Vec<Profile>,
+}
+
+impl Database {
+    fn mod_context<'a>(&'a self, name: &'a str) -> ModContext<'a> {
+        ModContext {
+            root: Path::new(&self.root_folder),
+            name,
+            disabled_folder: &self.disabled_folder,
+            library_folder: &self.library_folder,
+        }
+    }
+    
+    // Folder currently holding the files of `name`, wherever the strategy keeps it
+    fn mod_folder(&self, name: &str) -> Option<PathBuf> {
+        paths::check_name(name).ok()?;
+        let strategy = strategy::get(&self.mod_strategy)?;
+        strategy.search_dirs(&self.mod_context(name))
+            .into_iter()
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_dir())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -70,8 +167,15 @@ fn get_storage_location() -> String {
 }
 
 fn get_data_dir() -> PathBuf {
-    let location = get_storage_location();
+    #[cfg(test)]
+    if let Some(dir) = test_support::data_dir() {
+        return dir;
+    }
     
+    data_dir_for(&get_storage_location())
+}
+
+fn data_dir_for(location: &str) -> PathBuf {
     if location == "appdata" {
         if let Ok(appdata) = std::env::var("APPDATA").or_else(|_| std::env::var("HOME")) {
             let dir = Path::new(&appdata).join("ModManager");
@@ -101,40 +205,17 @@ fn load_games() -> Result<Vec<Game>, String> {
         return Ok(vec![]);
     }
     
-    let content = fs::read_to_string(&path)
-        .map_err(|e| e.to_string())?;
-    
-    let games: Vec<Game> = serde_json::from_str(&content)
-        .map_err(|e| e.to_string())?;
-    
-    Ok(games)
+    storage::read_json(&path)
 }
 
 #[tauri::command]
 fn add_game(name: String, description: String, preview: Option<String>) -> Result<Game, String> {
-    let mut games = load_games()?;
-    
-    let new_id = games.iter().map(|g| g.id).max().unwrap_or(0) + 1;
-    
-    let new_game = Game {
-        id: new_id,
-        name,
-        description,
-        preview,
-    };
-    
-    games.push(new_game.clone());
-    
-    let json = serde_json::to_string_pretty(&games)
-        .map_err(|e| e.to_string())?;
-    fs::write(&get_games_path(), json)
-        .map_err(|e| e.to_string())?;
-    
-    // Create default database for this game
     let default_db = Database {
+        schema_version: schema::CURRENT_VERSION,
         root_folder: String::from("C:\\Games\\Mods"),
         disabled_folder: String::from("_Disabled"),
-        mod_strategy: String::from("generic_rename"),
+        mod_strategy: String::from(strategy::DEFAULT_STRATEGY),
+        library_folder: String::new(),
         categories: vec![Category {
             id: 1,
             name: String::from("Root"),
@@ -144,12 +225,29 @@ fn add_game(name: String, description: String, preview: Option<String>) -> Resul
         mods: vec![],
         tags: vec![],
         tag_metadata: vec![],
+        profiles: vec![],
     };
     
-    let db_json = serde_json::to_string_pretty(&default_db)
-        .map_err(|e| e.to_string())?;
-    fs::write(&get_game_db_path(new_id), db_json)
-        .map_err(|e| e.to_string())?;
+    create_game(name, description, preview, &default_db)
+}
+
+fn create_game(name: String, description: String, preview: Option<String>, db: &Database) -> Result<Game, String> {
+    let mut games = load_games()?;
+    
+    let new_id = games.iter().map(|g| g.id).max().unwrap_or(0) + 1;
+    
+    let new_game = Game {
+        id: new_id,
+        name,
+        description,
+        preview,
+    };
+    
+    // Write the database first so games.json never lists a game without one
+    storage::write_json(&get_game_db_path(new_id), db)?;
+    
+    games.push(new_game.clone());
+    storage::write_json(&get_games_path(), &games)?;
     
     Ok(new_game)
 }
@@ -166,28 +264,24 @@ fn update_game(game_id: i64, name: String, description: String, preview: Option<
         return Err(format!("Game with id {} not found", game_id));
     }
     
-    let json = serde_json::to_string_pretty(&games)
-        .map_err(|e| e.to_string())?;
-    fs::write(&get_games_path(), json)
-        .map_err(|e| e.to_string())
+    storage::write_json(&get_games_path(), &games)
 }
 
 #[tauri::command]
-fn delete_game(game_id: i64) -> Result<(), String> {
+fn delete_game(game_id: i64, watcher: tauri::State<'_, ModWatcher>) -> Result<(), String> {
+    watcher.unwatch(game_id);
+    
     let mut games = load_games()?;
     games.retain(|g| g.id != game_id);
     
-    let json = serde_json::to_string_pretty(&games)
-        .map_err(|e| e.to_string())?;
-    fs::write(&get_games_path(), json)
-        .map_err(|e| e.to_string())?;
+    storage::write_json(&get_games_path(), &games)?;
     
     // Delete game database file
     let db_path = get_game_db_path(game_id);
-    if db_path.exists() {
-        fs::remove_file(&db_path)
-            .map_err(|e| format!("Failed to delete game data: {}", e))?;
-    }
+    storage::remove_json(&db_path)
+        .map_err(|e| format!("Failed to delete game data: {}", e))?;
+    backups::remove_snapshots(&db_path);
+    history::forget(game_id);
     
     Ok(())
 }
@@ -202,31 +296,31 @@ fn load_game_db(game_id: i64) -> Result<Database, String> {
         return Err(format!("Game database not found for game id {}", game_id));
     }
     
-    let content = fs::read_to_string(&path)
-        .map_err(|e| e.to_string())?;
+    schema::load_database(&path, strategy::DEFAULT_STRATEGY)
+}
+
+/// Saves a database edited in the UI, recording what changed so it can be
+/// undone.
+#[tauri::command]
+fn save_game_db(game_id: i64, db: Database) -> Result<(), String> {
+    let before = load_game_db(game_id).ok();
+    let after = serde_json::to_value(&db).map_err(|e| e.to_string())?;
     
-    let mut db: Database = serde_json::from_str(&content)
-        .map_err(|e| e.to_string())?;
+    write_game_db(game_id, db)?;
     
-    // Add fields if they don't exist (for backward compatibility)
-    if db.mod_strategy.is_empty() {
-        db.mod_strategy = String::from("generic_rename");
-    }
-    if db.disabled_folder.is_empty() {
-        db.disabled_folder = String::from("_Disabled");
+    if let Some(before) = before.and_then(|db| serde_json::to_value(db).ok()) {
+        history::record_edit(game_id, &before, &after)?;
     }
-    
-    Ok(db)
+    Ok(())
 }
 
-#[tauri::command]
-fn save_game_db(game_id: i64, db: Database) -> Result<(), String> {
+fn write_game_db(game_id: i64, db: Database) -> Result<(), String> {
     let path = get_game_db_path(game_id);
-    let json = serde_json::to_string_pretty(&db)
-        .map_err(|e| e.to_string())?;
     
-    fs::write(&path, json)
-        .map_err(|e| e.to_string())
+    backups::snapshot(&path, false)
+        .map_err(|e| format!("Failed to back up game database: {}", e))?;
+    
+    storage::write_json(&path, &db)
 }
 
 // ============ LEGACY FUNCTIONS (for backward compatibility) ============
@@ -241,9 +335,11 @@ fn load_db() -> Result<Database, String> {
     
     if !path.exists() {
         let default = Database {
+            schema_version: schema::CURRENT_VERSION,
             root_folder: String::from("C:\\Games\\Mods"),
             disabled_folder: String::from("_Disabled"),
-            mod_strategy: String::from("generic_rename"),
+            mod_strategy: String::from(strategy::DEFAULT_STRATEGY),
+            library_folder: String::new(),
             categories: vec![Category {
                 id: 1,
                 name: String::from("Root"),
@@ -253,139 +349,198 @@ fn load_db() -> Result<Database, String> {
             mods: vec![],
             tags: vec![],
             tag_metadata: vec![],
+            profiles: vec![],
         };
         
-        let json = serde_json::to_string_pretty(&default)
-            .map_err(|e| e.to_string())?;
-        fs::write(&path, json).map_err(|e| e.to_string())?;
+        storage::write_json(&path, &default)?;
         
         return Ok(default);
     }
     
-    let content = fs::read_to_string(&path)
-        .map_err(|e| e.to_string())?;
-    
-    let mut db: Database = serde_json::from_str(&content)
-        .map_err(|e| e.to_string())?;
-    
-    if db.mod_strategy.is_empty() {
-        db.mod_strategy = String::from("wuthering_waves");
-    }
-    if db.disabled_folder.is_empty() {
-        db.disabled_folder = String::from("_Disabled");
-    }
-    
-    Ok(db)
+    schema::load_database(&path, strategy::LEGACY_STRATEGY)
 }
 
 #[tauri::command]
 fn save_db(db: Database) -> Result<(), String> {
-    let path = get_db_path();
-    let json = serde_json::to_string_pretty(&db)
-        .map_err(|e| e.to_string())?;
-    
-    fs::write(&path, json)
-        .map_err(|e| e.to_string())
+    storage::write_json(&get_db_path(), &db)
 }
 
-// ============ MOD OPERATIONS ============
+#[tauri::command]
+fn has_legacy_db() -> bool {
+    get_db_path().exists()
+}
 
+// Converts mod-manager.json into a regular game. The legacy file is renamed
+// afterwards so the import only ever happens once.
 #[tauri::command]
-fn toggle_mod(root: String, name: String, enable: bool, strategy: String, disabled_folder: String) -> Result<(), String> {
-    let mod_path = Path::new(&root).join(&name);
+fn import_legacy_db(name: String, description: String) -> Result<Game, String> {
+    let path = get_db_path();
     
-    if !mod_path.exists() && !enable {
-        return Err(format!("Mod folder does not exist: {}", mod_path.display()));
+    if !path.exists() {
+        return Err(String::from("No legacy database to import"));
     }
     
-    match strategy.as_str() {
-        "wuthering_waves" => toggle_wuthering_waves(&mod_path, enable),
-        "generic_rename" => toggle_generic_rename(&mod_path, enable, &root, &disabled_folder),
-        _ => Err(format!("Unknown mod strategy: {}", strategy)),
-    }
+    let value = storage::read_json(&path)?;
+    let db = schema::upgrade(value, strategy::LEGACY_STRATEGY)?;
+    
+    let game = create_game(name, description, None, &db)?;
+    
+    let imported_path = get_data_dir().join("mod-manager.json.imported");
+    fs::rename(&path, &imported_path)
+        .map_err(|e| format!("Imported, but failed to retire legacy database: {}", e))?;
+    
+    Ok(game)
 }
 
-fn toggle_wuthering_waves(mod_path: &Path, enable: bool) -> Result<(), String> {
-    toggle_files_recursive(mod_path, enable, "ini")
+// ============ MOD OPERATIONS ============
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn toggle_mod(
+    root: String,
+    name: String,
+    enable: bool,
+    strategy: String,
+    disabled_folder: String,
+    library_folder: Option<String>,
+    game_id: Option<i64>,
+    watcher: tauri::State<'_, ModWatcher>
+) -> Result<Vec<ModChange>, String> {
+    let _pause = watcher.pause();
+    
+    // With a game the database supplies the folders and enforces exclusive tags
+    if let Some(game_id) = game_id {
+        paths::check_name(&name)?;
+        let mut db = load_game_db(game_id)?;
+        let strategy = strategy::resolve(&db.mod_strategy)?;
+        let mut changes = Vec::new();
+        
+        let result = exclusive::toggle(&mut db, strategy, &name, enable, &mut changes, None);
+        if !changes.is_empty() {
+            write_game_db(game_id, db)?;
+            let recorded = history::record_toggle(game_id, std::slice::from_ref(&name), enable, &changes);
+            return result.and(recorded).map(|_| changes);
+        }
+        
+        return result.map(|_| changes);
+    }
+    
+    let strategy = strategy::resolve(&strategy)?;
+    let ctx = ModContext {
+        root: Path::new(&root),
+        name: &name,
+        disabled_folder: &disabled_folder,
+        library_folder: library_folder.as_deref().unwrap_or(""),
+    };
+    paths::check_context(&ctx)?;
+    
+    apply_toggle(strategy, &ctx, enable)?;
+    
+    Ok(vec![ModChange {
+        name,
+        enabled: enable,
+        exclusive_tag: None,
+        caused_by: None,
+    }])
 }
 
-fn toggle_generic_rename(mod_path: &Path, enable: bool, root: &str, disabled_folder: &str) -> Result<(), String> {
-    let root_path = Path::new(root);
-    let disabled_path = root_path.join(disabled_folder);
+fn apply_toggle(strategy: &dyn ModStrategy, ctx: &ModContext, enable: bool) -> Result<(), String> {
+    let mod_path = ctx.mod_path();
     
-    if !disabled_path.exists() {
-        fs::create_dir_all(&disabled_path)
-            .map_err(|e| format!("Failed to create disabled folder: {}", e))?;
+    if !mod_path.exists() && !enable {
+        return Err(format!("Mod folder does not exist: {}", mod_path.display()));
     }
     
-    let folder_name = mod_path.file_name()
-        .ok_or_else(|| "Cannot get folder name".to_string())?;
-    
     if enable {
-        let source = disabled_path.join(folder_name);
-        if source.exists() {
-            fs::rename(&source, mod_path)
-                .map_err(|e| format!("Failed to enable mod: {}", e))?;
-        }
+        strategy.enable(ctx)
     } else {
-        let destination = disabled_path.join(folder_name);
-        if mod_path.exists() {
-            fs::rename(mod_path, &destination)
-                .map_err(|e| format!("Failed to disable mod: {}", e))?;
-        }
+        strategy.disable(ctx)
     }
-    
-    Ok(())
 }
 
-fn toggle_files_recursive(dir: &Path, enable: bool, extension: &str) -> Result<(), String> {
-    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
-        let entry = entry.map_err(|e| e.to_string())?;
-        let path = entry.path();
-        
-        if path.is_dir() {
-            toggle_files_recursive(&path, enable, extension)?;
-        } else if let Some(ext) = path.extension() {
-            let ext_str = ext.to_string_lossy();
-            
-            if enable {
-                if ext_str == "bak" {
-                    if let Some(stem) = path.file_stem() {
-                        let stem_str = stem.to_string_lossy();
-                        if stem_str.ends_with(&format!(".{}", extension)) {
-                            let new_path = path.with_extension("");
-                            fs::rename(&path, &new_path).map_err(|e| e.to_string())?;
-                        }
-                    }
-                }
-            } else {
-                if ext_str == extension {
-                    let new_path = PathBuf::from(format!("{}.bak", path.display()));
-                    fs::rename(&path, &new_path).map_err(|e| e.to_string())?;
-                }
-            }
-        }
-    }
+#[tauri::command]
+fn is_mod_enabled(
+    root: String,
+    name: String,
+    strategy: String,
+    disabled_folder: String,
+    library_folder: Option<String>
+) -> Result<bool, String> {
+    let strategy = strategy::resolve(&strategy)?;
+    let ctx = ModContext {
+        root: Path::new(&root),
+        name: &name,
+        disabled_folder: &disabled_folder,
+        library_folder: library_folder.as_deref().unwrap_or(""),
+    };
+    paths::check_context(&ctx)?;
     
-    Ok(())
+    Ok(strategy.is_enabled(&ctx))
 }
 
 #[tauri::command]
-fn delete_mod(root: String, name: String) -> Result<(), String> {
-    let mod_path = Path::new(&root).join(&name);
+fn list_strategies() -> Vec<StrategyInfo> {
+    strategy::all().iter().map(|s| s.describe()).collect()
+}
+
+// With a game, the record goes into the recycle bin with the files, so a
+// restore brings back notes and tags, and leaves the database.
+fn trash_mod(root: &str, name: &str, game_id: Option<i64>) -> Result<Option<DeletedMod>, String> {
+    if let Some(game_id) = game_id {
+        return history::trash_mod(game_id, root, name)
+            .map(Some)
+            .map_err(|e| format!("Failed to delete mod: {}", e));
+    }
+    
+    let mod_path = paths::mod_path(root, name)?;
     
     if !mod_path.exists() {
         return Err(format!("Mod folder does not exist: {}", mod_path.display()));
     }
     
-    fs::remove_dir_all(&mod_path)
+    trash::move_to_trash(&mod_path, name, None, None)
+        .map(|_| None)
         .map_err(|e| format!("Failed to delete mod: {}", e))
 }
 
 #[tauri::command]
-fn rename_mod(root: String, old_name: String, new_name: String) -> Result<(), String> {
-    let old_path = Path::new(&root).join(&old_name);
-    let new_path = Path::new(&root).join(&new_name);
+fn delete_mod(
+    root: String,
+    name: String,
+    game_id: Option<i64>,
+    watcher: tauri::State<'_, ModWatcher>
+) -> Result<(), String> {
+    let _pause = watcher.pause();
+    if let (Some(game_id), Some(deleted)) = (game_id, trash_mod(&root, &name, game_id)?) {
+        history::record(game_id, format!("Delete {}", name), Change::Delete { mods: vec![deleted] })?;
+    }
+    
+    Ok(())
+}
+
+#[tauri::command]
+fn rename_mod(
+    root: String,
+    old_name: String,
+    new_name: String,
+    game_id: Option<i64>,
+    watcher: tauri::State<'_, ModWatcher>
+) -> Result<(), String> {
+    let _pause = watcher.pause();
+    
+    // With a game the record is renamed too, so the rename can be undone as one
+    if let Some(game_id) = game_id {
+        history::rename_entry(game_id, &root, &old_name, &new_name)?;
+        history::record(game_id, format!("Rename {} to {}", old_name, new_name), Change::Rename {
+            root,
+            old_name,
+            new_name,
+        })?;
+        return Ok(());
+    }
+    
+    let old_path = paths::entry_path(&root, &old_name)?;
+    let new_path = paths::entry_path(&root, &new_name)?;
     
     if !old_path.exists() {
         return Err(format!("Mod folder does not exist: {}", old_path.display()));
@@ -400,9 +555,17 @@ fn rename_mod(root: String, old_name: String, new_name: String) -> Result<(), St
 }
 
 #[tauri::command]
-async fn copy_mod(source: String, dest_root: String, dest_name: String, window: tauri::Window) -> Result<(), String> {
+async fn copy_mod(
+    source: String,
+    dest_root: String,
+    dest_name: String,
+    window: tauri::Window,
+    jobs: tauri::State<'_, JobManager>,
+    watcher: tauri::State<'_, ModWatcher>
+) -> Result<(), String> {
+    let _pause = watcher.pause();
     let source_path = Path::new(&source);
-    let dest_path = Path::new(&dest_root).join(&dest_name);
+    let dest_path = paths::entry_path(&dest_root, &dest_name)?;
     
     if !source_path.exists() {
         return Err(format!("Source folder does not exist: {}", source_path.display()));
@@ -415,19 +578,44 @@ async fn copy_mod(source: String, dest_root: String, dest_name: String, window:
     fs::create_dir_all(&dest_path)
         .map_err(|e| format!("Failed to create destination: {}", e))?;
     
-    let _ = window.emit("copy-progress", "Copying files...");
-    copy_dir_recursive(source_path, &dest_path)
-        .map_err(|e| format!("Failed to copy mod: {}", e))?;
+    let job = jobs.start(&window, "copy", &dest_name);
+    let result = transfer::copy_dir(source_path, &dest_path, &mut |progress| {
+        let _ = window.emit("copy-progress", progress.clone());
+        job.progress(progress)
+    });
+    
+    // Don't leave a partial copy behind
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&dest_path);
+    }
+    job.finish(result)?;
     
     let _ = window.emit("copy-complete", "Copy complete!");
     
     Ok(())
 }
 
+/// Moves the folder `source` into a mod folder as `dest_name`. With a game,
+/// `record` is added to its database, so the move and the new record are
+/// undone together.
 #[tauri::command]
-async fn move_mod(source: String, dest_root: String, dest_name: String) -> Result<(), String> {
+#[allow(clippy::too_many_arguments)]
+async fn move_mod(
+    source: String,
+    dest_root: String,
+    dest_name: String,
+    game_id: Option<i64>,
+    record: Option<Mod>,
+    window: tauri::Window,
+    jobs: tauri::State<'_, JobManager>,
+    watcher: tauri::State<'_, ModWatcher>
+) -> Result<(), String> {
+    let _pause = watcher.pause();
     let source_path = Path::new(&source);
-    let dest_path = Path::new(&dest_root).join(&dest_name);
+    let dest_path = match game_id {
+        Some(game_id) => paths::game_entry_path(&load_game_db(game_id)?, &dest_root, &dest_name)?,
+        None => paths::entry_path(&dest_root, &dest_name)?,
+    };
     
     if !source_path.exists() {
         return Err(format!("Source folder does not exist: {}", source_path.display()));
@@ -442,31 +630,28 @@ async fn move_mod(source: String, dest_root: String, dest_name: String) -> Resul
             .map_err(|e| format!("Failed to create destination directory: {}", e))?;
     }
     
-    fs::rename(source_path, &dest_path)
-        .map_err(|e| format!("Failed to move mod: {}", e))?;
-    
-    Ok(())
-}
-
-fn copy_dir_recursive(src: &Path, dst: &Path) -> std::io::Result<()> {
-    if !dst.exists() {
-        fs::create_dir(dst)?;
-    }
+    // Only a move across drives copies, and only then is there progress to show
+    let job = jobs.start(&window, "move", &dest_name);
+    let result = transfer::move_dir(source_path, &dest_path, &mut |progress| {
+        let _ = window.emit("copy-progress", progress.clone());
+        job.progress(progress)
+    });
     
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let ty = entry.file_type()?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
-        
-        if ty.is_dir() {
-            copy_dir_recursive(&src_path, &dst_path)?;
-        } else {
-            fs::copy(&src_path, &dst_path)?;
-        }
-    }
+    let result = match (game_id, result) {
+        (Some(game_id), Ok(())) => record.clone()
+            .map_or(Ok(()), |record| history::add_record(game_id, record))
+            .and_then(|_| {
+                let verb = if record.is_some() { "Add" } else { "Move" };
+                history::record(game_id, format!("{} {}", verb, dest_name), Change::Move {
+                    source,
+                    destination: dest_path.to_string_lossy().to_string(),
+                    record,
+                })
+            }),
+        (_, result) => result,
+    };
     
-    Ok(())
+    job.finish(result)
 }
 
 // ============ UTILITY FUNCTIONS ============
@@ -490,126 +675,130 @@ fn get_local_path() -> Result<String, String> {
 
 #[tauri::command]
 fn check_db_exists(location: String) -> Result<bool, String> {
-    let path = if location == "appdata" {
-        let appdata = get_appdata_path()?;
-        Path::new(&appdata).join("mod-manager.json")
-    } else {
-        Path::new(".").join("mod-manager.json")
-    };
-    
-    Ok(path.exists())
+    Ok(datadir::has_data(&data_dir_for(&location)))
 }
 
+// Moves the whole data directory (games, game databases, snapshots and any
+// legacy database) from one location to the other, replacing what is there.
 #[tauri::command]
 fn migrate_data(from: String, to: String, delete_old: bool, create_backup: bool) -> Result<(), String> {
-    let from_path = if from == "appdata" {
-        let appdata = get_appdata_path()?;
-        Path::new(&appdata).join("mod-manager.json")
-    } else {
-        Path::new(".").join("mod-manager.json")
-    };
+    if from == to {
+        return Err(String::from("Source and destination locations are the same"));
+    }
     
-    let to_path = if to == "appdata" {
-        let appdata = get_appdata_path()?;
-        let dir = Path::new(&appdata);
-        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
-        dir.join("mod-manager.json")
-    } else {
-        Path::new(".").join("mod-manager.json")
-    };
+    let from_dir = data_dir_for(&from);
+    let to_dir = data_dir_for(&to);
     
-    if to_path.exists() && !delete_old {
-        let backup_path = to_path.with_extension("json.backup");
-        fs::copy(&to_path, &backup_path)
-            .map_err(|e| format!("Failed to create backup: {}", e))?;
+    if create_backup && datadir::has_data(&to_dir) {
+        datadir::backup_data(&to_dir)?;
     }
     
-    if from_path.exists() {
-        let content = fs::read_to_string(&from_path)
-            .map_err(|e| format!("Failed to read source data: {}", e))?;
-        
-        fs::write(&to_path, content)
-            .map_err(|e| format!("Failed to write data: {}", e))?;
-        
-        if create_backup && !delete_old {
-            let backup_path = from_path.with_extension("json.backup");
-            let content = fs::read_to_string(&from_path)
-                .map_err(|e| format!("Failed to read for backup: {}", e))?;
-            fs::write(&backup_path, content)
-                .map_err(|e| format!("Failed to create backup: {}", e))?;
-        }
-        
-        if delete_old {
-            fs::remove_file(&from_path)
-                .map_err(|e| format!("Failed to delete old data: {}", e))?;
-        }
+    datadir::replace_data(&from_dir, &to_dir)?;
+    
+    if delete_old {
+        datadir::remove_data(&from_dir)
+            .map_err(|e| format!("Data copied, but failed to delete old data: {}", e))?;
     }
     
     Ok(())
 }
 
 #[tauri::command]
-fn get_db_summary(location: String) -> Result<String, String> {
-    let path = if location == "appdata" {
-        let appdata = get_appdata_path()?;
-        Path::new(&appdata).join("mod-manager.json")
-    } else {
-        Path::new(".").join("mod-manager.json")
-    };
+fn discard_data(location: String, create_backup: bool) -> Result<(), String> {
+    let dir = data_dir_for(&location);
     
-    if !path.exists() {
-        return Ok(String::from("No data"));
+    if create_backup {
+        datadir::backup_data(&dir)?;
     }
     
-    let content = fs::read_to_string(&path)
-        .map_err(|e| e.to_string())?;
-    
-    let db: Database = serde_json::from_str(&content)
-        .map_err(|e| e.to_string())?;
+    datadir::remove_data(&dir)
+}
+
+#[tauri::command]
+fn get_db_summary(location: String) -> Result<String, String> {
+    let dir = data_dir_for(&location);
     
-    let summary = serde_json::json!({
-        "categories": db.categories.len(),
-        "mods": db.mods.len(),
-        "root_folder": db.root_folder,
-        "mod_strategy": db.mod_strategy
-    });
+    if !datadir::has_data(&dir) {
+        return Ok(String::from("No data"));
+    }
     
-    serde_json::to_string_pretty(&summary)
+    serde_json::to_string_pretty(&datadir::summarize(&dir))
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn toggle_mods_bulk(
+#[allow(clippy::too_many_arguments)]
+async fn toggle_mods_bulk(
     root: String, 
     mod_names: Vec<String>, 
     enable: bool, 
     strategy: String, 
-    disabled_folder: String
-) -> Result<Vec<String>, String> {
-    let mut errors = Vec::new();
-    
-    for name in mod_names {
-        if let Err(e) = toggle_mod(
-            root.clone(), 
-            name.clone(), 
-            enable, 
-            strategy.clone(), 
-            disabled_folder.clone()
-        ) {
-            errors.push(format!("{}: {}", name, e));
+    disabled_folder: String,
+    library_folder: Option<String>,
+    game_id: Option<i64>,
+    window: tauri::Window,
+    jobs: tauri::State<'_, JobManager>,
+    watcher: tauri::State<'_, ModWatcher>
+) -> Result<ToggleReport, String> {
+    let _pause = watcher.pause();
+    
+    // Every toggle goes through a journal, so a failure or cancellation
+    // rolls the whole batch back
+    let (journal, db) = match game_id {
+        Some(game_id) => {
+            let db = load_game_db(game_id)?;
+            let journal = Journal::begin(
+                Some(game_id),
+                &db.root_folder,
+                &db.disabled_folder,
+                &db.library_folder,
+                &db.mod_strategy,
+                mod_names,
+                enable
+            )?;
+            (journal, Some(db))
         }
-    }
+        None => {
+            let journal = Journal::begin(
+                None,
+                &root,
+                &disabled_folder,
+                library_folder.as_deref().unwrap_or(""),
+                &strategy,
+                mod_names,
+                enable
+            )?;
+            (journal, None)
+        }
+    };
     
-    Ok(errors)
+    let job = jobs.start(&window, "toggle", if enable { "Enable mods" } else { "Disable mods" });
+    journal::complete(journal, db, job)
 }
 
 #[tauri::command]
-fn delete_mods_bulk(root: String, mod_names: Vec<String>) -> Result<Vec<String>, String> {
+fn delete_mods_bulk(
+    root: String,
+    mod_names: Vec<String>,
+    game_id: Option<i64>,
+    watcher: tauri::State<'_, ModWatcher>
+) -> Result<Vec<String>, String> {
+    let _pause = watcher.pause();
     let mut errors = Vec::new();
+    let mut deleted = Vec::new();
     
     for name in mod_names {
-        if let Err(e) = delete_mod(root.clone(), name.clone()) {
-            errors.push(format!("{}: {}", name, e));
+        match trash_mod(&root, &name, game_id) {
+            Ok(Some(entry)) => deleted.push(entry),
+            Ok(None) => {}
+            Err(e) => errors.push(format!("{}: {}", name, e)),
+        }
+    }
+    
+    if let (Some(game_id), false) = (game_id, deleted.is_empty()) {
+        let label = format!("Delete {} mods", deleted.len());
+        if let Err(e) = history::record(game_id, label, Change::Delete { mods: deleted }) {
+            errors.push(e);
         }
     }
     
@@ -627,7 +816,7 @@ fn set_data_location(location: String) -> Result<(), String> {
     let json = serde_json::to_string_pretty(&config)
         .map_err(|e| e.to_string())?;
     
-    fs::write(&config_path, json)
+    storage::write_atomic(&config_path, json.as_bytes())
         .map_err(|e| format!("Failed to save storage config: {}", e))
 }
 
@@ -637,61 +826,50 @@ fn get_data_location() -> Result<String, String> {
 }
 
 #[tauri::command]
-async fn extract_archive(archive_path: String, dest_root: String, dest_name: String, window: tauri::Window) -> Result<(), String> {
-    let archive_file = fs::File::open(&archive_path)
-        .map_err(|e| format!("Failed to open archive: {}", e))?;
-    
-    let mut archive = ZipArchive::new(archive_file)
-        .map_err(|e| format!("Failed to read archive: {}", e))?;
-    
-    let dest_path = Path::new(&dest_root).join(&dest_name);
+async fn extract_archive(
+    archive_path: String,
+    dest_root: String,
+    dest_name: String,
+    variants: Option<Vec<String>>,
+    window: tauri::Window,
+    jobs: tauri::State<'_, JobManager>,
+    watcher: tauri::State<'_, ModWatcher>
+) -> Result<Vec<String>, String> {
+    let _pause = watcher.pause();
+    let dest_path = paths::entry_path(&dest_root, &dest_name)?;
+    let dest_root = Path::new(&dest_root);
     
-    if dest_path.exists() {
+    // Variants are installed under their own names
+    if variants.is_none() && dest_path.exists() {
         return Err(format!("Destination already exists: {}", dest_path.display()));
     }
     
-    fs::create_dir_all(&dest_path)
+    // Extract next to the mods first, so the layout can be inspected and the
+    // mods renamed into place without copying
+    let staging = dest_root.join(format!(".{}.extracting", dest_name));
+    let _ = fs::remove_dir_all(&staging);
+    fs::create_dir_all(&staging)
         .map_err(|e| format!("Failed to create destination: {}", e))?;
     
-    let total_files = archive.len();
+    let job = jobs.start(&window, "extract", &dest_name);
+    let result = archive::extract(Path::new(&archive_path), &staging, |progress| {
+        let percent = progress.files_done * 100 / progress.files_total.max(1);
+        let _ = window.emit("extract-progress", percent as u32);
+        job.progress(progress)
+    }).and_then(|_| archive::install_mods(&staging, dest_root, &dest_name, variants.as_deref()));
     
-    for i in 0..total_files {
-        let mut file = archive.by_index(i)
-            .map_err(|e| format!("Failed to read archive entry: {}", e))?;
-        
-        let outpath = match file.enclosed_name() {
-            Some(path) => dest_path.join(path),
-            None => continue,
-        };
-        
-        let progress = ((i + 1) as f32 / total_files as f32 * 100.0) as u32;
-        let _ = window.emit("extract-progress", progress);
-        
-        if file.name().ends_with('/') {
-            fs::create_dir_all(&outpath)
-                .map_err(|e| format!("Failed to create directory: {}", e))?;
-        } else {
-            if let Some(p) = outpath.parent() {
-                if !p.exists() {
-                    fs::create_dir_all(p)
-                        .map_err(|e| format!("Failed to create parent directory: {}", e))?;
-                }
-            }
-            let mut outfile = fs::File::create(&outpath)
-                .map_err(|e| format!("Failed to create file: {}", e))?;
-            io::copy(&mut file, &mut outfile)
-                .map_err(|e| format!("Failed to extract file: {}", e))?;
-        }
-    }
+    // Whatever wasn't moved into place is junk or a half-extracted mod
+    let _ = fs::remove_dir_all(&staging);
+    let names = job.finish(result)?;
     
     let _ = window.emit("extract-complete", "Extraction complete!");
     
-    Ok(())
+    Ok(names)
 }
 
 #[tauri::command]
 fn save_preview(root: String, name: String, data: String) -> Result<(), String> {
-    let mod_path = Path::new(&root).join(&name);
+    let mod_path = paths::mod_path(&root, &name)?;
     
     if !mod_path.exists() {
         fs::create_dir_all(&mod_path).map_err(|e| e.to_string())?;
@@ -702,17 +880,17 @@ fn save_preview(root: String, name: String, data: String) -> Result<(), String>
     let bytes = base64_decode(data)
         .map_err(|e| format!("Failed to decode image: {}", e))?;
     
-    let preview_path = mod_path.join("preview.png");
+    let preview_path = paths::contained(&mod_path, "preview.png")?;
     fs::write(&preview_path, bytes)
         .map_err(|e| format!("Failed to save preview: {}", e))
 }
 
 #[tauri::command]
 fn load_preview(root: String, name: String) -> Result<Option<String>, String> {
-    let mod_path = Path::new(&root).join(&name);
+    let mod_path = paths::mod_path(&root, &name)?;
     
     for ext in &["png", "jpg", "jpeg", "bmp", "gif"] {
-        let preview_path = mod_path.join(format!("preview.{}", ext));
+        let preview_path = paths::contained(&mod_path, &format!("preview.{}", ext))?;
         if preview_path.exists() {
             let bytes = fs::read(&preview_path)
                 .map_err(|e| e.to_string())?;
@@ -726,21 +904,21 @@ fn load_preview(root: String, name: String) -> Result<Option<String>, String> {
 
 #[tauri::command]
 fn save_notes(root: String, name: String, notes: String) -> Result<(), String> {
-    let mod_path = Path::new(&root).join(&name);
+    let mod_path = paths::mod_path(&root, &name)?;
     
     if !mod_path.exists() {
         fs::create_dir_all(&mod_path).map_err(|e| e.to_string())?;
     }
     
-    let notes_path = mod_path.join("notes.txt");
+    let notes_path = paths::contained(&mod_path, "notes.txt")?;
     fs::write(&notes_path, notes)
         .map_err(|e| format!("Failed to save notes: {}", e))
 }
 
 #[tauri::command]
 fn load_notes(root: String, name: String) -> Result<String, String> {
-    let mod_path = Path::new(&root).join(&name);
-    let notes_path = mod_path.join("notes.txt");
+    let mod_path = paths::mod_path(&root, &name)?;
+    let notes_path = paths::contained(&mod_path, "notes.txt")?;
     
     if notes_path.exists() {
         fs::read_to_string(&notes_path)
@@ -835,6 +1013,12 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
+        .manage(ModWatcher::default())
+        .manage(JobManager::default())
+        .setup(|app| {
+            watcher::watch_all_games(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Game management
             load_games,
@@ -843,16 +1027,37 @@ pub fn run() {
             delete_game,
             load_game_db,
             save_game_db,
+            backups::list_backups,
+            backups::diff_backup,
+            backups::restore_backup,
             // Legacy database (for backward compatibility)
             load_db,
             save_db,
+            has_legacy_db,
+            import_legacy_db,
             // Mod operations
             toggle_mod,
+            is_mod_enabled,
+            list_strategies,
+            profiles::apply_profile,
+            profiles::save_profile,
+            presets::get_mod_variables,
+            presets::set_mod_variables,
+            presets::save_mod_preset,
+            presets::apply_mod_preset,
+            migoto::inspect_mod,
+            migoto::rebind_mod_key,
+            conflicts::detect_conflicts,
+            conflicts::list_mod_keys,
+            scan::scan_mod_folder,
+            watcher::watch_game,
+            watcher::unwatch_game,
             delete_mod,
             rename_mod,
             copy_mod,
             move_mod,
             extract_archive,
+            archive::inspect_archive,
             save_preview,
             load_preview,
             save_notes,
@@ -864,12 +1069,57 @@ pub fn run() {
             get_local_path,
             check_db_exists,
             migrate_data,
+            discard_data,
             set_data_location,
             get_data_location,
             get_db_summary,
             toggle_mods_bulk,
             delete_mods_bulk,
+            history::history,
+            history::undo,
+            history::redo,
+            history::clear_history,
+            jobs::cancel_job,
+            jobs::list_jobs,
+            journal::list_journals,
+            journal::resume_journal,
+            journal::revert_journal,
+            trash::list_trash,
+            trash::restore_from_trash,
+            trash::purge_trash,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestDataDir;
+
+    #[test]
+    fn loads_and_saves_a_game_with_an_unknown_strategy() {
+        let _data = TestDataDir::new("unknown-strategy");
+        let db: Database = serde_json::from_value(serde_json::json!({
+            "root_folder": "C:\\Games\\Mods",
+            "disabled_folder": "_Disabled",
+            "mod_strategy": "unreal_pak",
+            "categories": [],
+            "mods": [],
+            "tags": [],
+            "tag_metadata": [],
+            "profiles": [],
+        })).unwrap();
+        write_game_db(1, db).unwrap();
+
+        let mut db = load_game_db(1).unwrap();
+        assert_eq!(db.mod_strategy, "unreal_pak");
+
+        db.disabled_folder = String::from("_Off");
+        save_game_db(1, db).unwrap();
+        assert_eq!(load_game_db(1).unwrap().disabled_folder, "_Off");
+
+        let error = strategy::resolve("unreal_pak").err().unwrap();
+        assert!(error.starts_with("Unknown mod strategy: unreal_pak"), "{}", error);
+    }
+}
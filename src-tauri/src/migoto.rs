@@ -0,0 +1,745 @@
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::watcher::ModWatcher;
+use crate::{paths, storage};
+
+// Parser and data model for the 3DMigoto ini files that Wuthering Waves mods
+// (and other XXMI games) ship. Only what the manager needs is interpreted;
+// command lists are kept as plain entries.
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SectionKind {
+    TextureOverride,
+    ShaderOverride,
+    ShaderRegex,
+    Key,
+    Constants,
+    Resource,
+    CommandList,
+    Present,
+    Other,
+}
+
+impl SectionKind {
+    fn from_name(name: &str) -> SectionKind {
+        let lower = name.to_lowercase();
+        let prefixes = [
+            ("textureoverride", SectionKind::TextureOverride),
+            ("shaderoverride", SectionKind::ShaderOverride),
+            ("shaderregex", SectionKind::ShaderRegex),
+            ("key", SectionKind::Key),
+            ("constants", SectionKind::Constants),
+            ("resource", SectionKind::Resource),
+            ("commandlist", SectionKind::CommandList),
+            ("present", SectionKind::Present),
+        ];
+
+        prefixes.iter()
+            .find(|(prefix, _)| lower.starts_with(prefix))
+            .map(|(_, kind)| *kind)
+            .unwrap_or(SectionKind::Other)
+    }
+}
+
+/// A `key = value` line. `line` is 1-based, as shown by text editors.
+#[derive(Debug, Serialize, Clone)]
+pub struct Entry {
+    pub key: String,
+    pub value: String,
+    pub line: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Section {
+    pub name: String,
+    pub kind: SectionKind,
+    pub line: usize,
+    pub entries: Vec<Entry>,
+}
+
+impl Section {
+    /// Value of the first entry named `key`, compared case-insensitively
+    /// like 3DMigoto does.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.iter()
+            .find(|e| e.key.eq_ignore_ascii_case(key))
+            .map(|e| e.value.as_str())
+    }
+
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Entry> {
+        self.entries.iter().filter(move |e| e.key.eq_ignore_ascii_case(key))
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct IniFile {
+    // `namespace = ...` before the first section
+    pub namespace: Option<String>,
+    pub sections: Vec<Section>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HashOverride {
+    pub hash: String,
+    pub section: String,
+    pub kind: SectionKind,
+    pub match_first_index: Option<String>,
+    pub file: String,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Modifier {
+    Any,
+    Required,
+    Forbidden,
+}
+
+/// A parsed `key = ...` value such as `no_modifiers VK_F1` or `ctrl shift 7`.
+/// Left and right variants of a modifier are treated as the modifier itself.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct KeyCombo {
+    pub key: String,
+    pub ctrl: Modifier,
+    pub alt: Modifier,
+    pub shift: Modifier,
+    pub windows: Modifier,
+    pub label: String,
+}
+
+impl KeyCombo {
+    pub fn parse(value: &str) -> KeyCombo {
+        let mut combo = KeyCombo {
+            key: String::new(),
+            ctrl: Modifier::Any,
+            alt: Modifier::Any,
+            shift: Modifier::Any,
+            windows: Modifier::Any,
+            label: String::new(),
+        };
+
+        for token in value.split_whitespace() {
+            let lower = token.to_lowercase();
+            let (name, state) = match lower.strip_prefix("no_") {
+                Some(name) => (name, Modifier::Forbidden),
+                None => (lower.as_str(), Modifier::Required),
+            };
+
+            match name {
+                "ctrl" | "lctrl" | "rctrl" | "control" => combo.ctrl = state,
+                "alt" | "lalt" | "ralt" => combo.alt = state,
+                "shift" | "lshift" | "rshift" => combo.shift = state,
+                "windows" | "win" | "lwin" | "rwin" => combo.windows = state,
+                "modifiers" if state == Modifier::Forbidden => {
+                    combo.ctrl = Modifier::Forbidden;
+                    combo.alt = Modifier::Forbidden;
+                    combo.shift = Modifier::Forbidden;
+                    combo.windows = Modifier::Forbidden;
+                }
+                _ => {
+                    let upper = token.to_uppercase();
+                    combo.key = upper.strip_prefix("VK_").unwrap_or(&upper).to_string();
+                }
+            }
+        }
+
+        let mut parts: Vec<&str> = Vec::new();
+        for (state, name) in [(combo.ctrl, "Ctrl"), (combo.alt, "Alt"), (combo.shift, "Shift"), (combo.windows, "Win")] {
+            if state == Modifier::Required {
+                parts.push(name);
+            }
+        }
+        parts.push(&combo.key);
+        combo.label = parts.join("+");
+
+        combo
+    }
+
+    /// Whether one key press can trigger both combos.
+    pub fn overlaps(&self, other: &KeyCombo) -> bool {
+        let compatible = |a: Modifier, b: Modifier| {
+            !matches!((a, b), (Modifier::Required, Modifier::Forbidden) | (Modifier::Forbidden, Modifier::Required))
+        };
+
+        !self.key.is_empty()
+            && self.key == other.key
+            && compatible(self.ctrl, other.ctrl)
+            && compatible(self.alt, other.alt)
+            && compatible(self.shift, other.shift)
+            && compatible(self.windows, other.windows)
+    }
+}
+
+// `[KeyCycleOutfit]` becomes "Cycle Outfit".
+fn describe_key_section(name: &str) -> String {
+    let rest = name.get(3..).unwrap_or("");
+    let mut words = String::new();
+    let mut prev: Option<char> = None;
+
+    for c in rest.chars() {
+        if c == '_' || c == '-' || c == '.' {
+            words.push(' ');
+        } else {
+            if c.is_uppercase() && prev.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit()) {
+                words.push(' ');
+            }
+            words.push(c);
+        }
+        prev = Some(c);
+    }
+
+    let words = words.split_whitespace().collect::<Vec<_>>().join(" ");
+    if words.is_empty() { name.to_string() } else { words }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct KeyBinding {
+    pub key: String,
+    pub combo: KeyCombo,
+    pub description: String,
+    pub back: Option<String>,
+    // `type = cycle|toggle|hold|activate`, absent means activate
+    pub binding_type: Option<String>,
+    pub variables: Vec<String>,
+    pub section: String,
+    pub line: usize,
+    pub file: String,
+}
+
+/// A `global persist $name = default` declaration in `[Constants]`, whose
+/// value 3DMigoto saves to d3dx_user.ini.
+#[derive(Debug, Serialize, Clone)]
+pub struct PersistentVariable {
+    pub name: String,
+    pub default: String,
+    pub file: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct IniSummary {
+    pub file: String,
+    // Renamed to .ini.bak or prefixed with DISABLED, so 3DMigoto skips it
+    pub disabled: bool,
+    pub namespace: Option<String>,
+    pub sections: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ModInspection {
+    pub name: String,
+    pub files: Vec<IniSummary>,
+    pub hashes: Vec<HashOverride>,
+    pub keybindings: Vec<KeyBinding>,
+    pub namespaces: Vec<String>,
+}
+
+pub fn parse(content: &str) -> IniFile {
+    let mut namespace = None;
+    let mut sections: Vec<Section> = Vec::new();
+
+    for (i, raw) in content.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line = raw.trim();
+
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            let name = line[1..line.len() - 1].trim().to_string();
+            sections.push(Section {
+                kind: SectionKind::from_name(&name),
+                name,
+                line: i + 1,
+                entries: vec![],
+            });
+            continue;
+        }
+
+        // `if`, `else`, `endif` and similar flow control carry no `=`
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => (line, ""),
+        };
+
+        match sections.last_mut() {
+            Some(section) => section.entries.push(Entry {
+                key: key.to_string(),
+                value: value.to_string(),
+                line: i + 1,
+            }),
+            None if key.eq_ignore_ascii_case("namespace") => {
+                namespace = Some(value.to_string());
+            }
+            None => {}
+        }
+    }
+
+    IniFile { namespace, sections }
+}
+
+pub fn parse_file(path: &Path) -> io::Result<IniFile> {
+    let bytes = fs::read(path)?;
+    Ok(parse(&String::from_utf8_lossy(&bytes)))
+}
+
+impl IniFile {
+    pub fn hashes(&self, file: &str) -> Vec<HashOverride> {
+        self.sections.iter()
+            .filter(|s| matches!(s.kind, SectionKind::TextureOverride | SectionKind::ShaderOverride))
+            .filter_map(|s| {
+                Some(HashOverride {
+                    hash: s.get("hash")?.to_lowercase(),
+                    section: s.name.clone(),
+                    kind: s.kind,
+                    match_first_index: s.get("match_first_index").map(str::to_string),
+                    file: file.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn persistent_variables(&self, file: &str) -> Vec<PersistentVariable> {
+        self.sections.iter()
+            .filter(|s| s.kind == SectionKind::Constants)
+            .flat_map(|s| s.entries.iter())
+            .filter_map(|entry| {
+                let mut words: Vec<&str> = entry.key.split_whitespace().collect();
+                let name = words.pop().filter(|name| name.starts_with('$'))?;
+
+                words.iter().any(|w| w.eq_ignore_ascii_case("persist")).then(|| PersistentVariable {
+                    name: name.to_string(),
+                    default: entry.value.clone(),
+                    file: file.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn key_bindings(&self, file: &str) -> Vec<KeyBinding> {
+        let mut bindings = Vec::new();
+
+        for section in self.sections.iter().filter(|s| s.kind == SectionKind::Key) {
+            let variables: Vec<String> = section.entries.iter()
+                .filter(|e| e.key.starts_with('$'))
+                .map(|e| e.key.clone())
+                .collect();
+
+            // A section may list several keys that all trigger it
+            for entry in section.get_all("key") {
+                bindings.push(KeyBinding {
+                    key: entry.value.clone(),
+                    combo: KeyCombo::parse(&entry.value),
+                    description: describe_key_section(&section.name),
+                    back: section.get("back").map(str::to_string),
+                    binding_type: section.get("type").map(str::to_string),
+                    variables: variables.clone(),
+                    section: section.name.clone(),
+                    line: entry.line,
+                    file: file.to_string(),
+                });
+            }
+        }
+
+        bindings
+    }
+}
+
+pub fn is_disabled_ini(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+    name.ends_with(".ini.bak") || name.starts_with("disabled")
+}
+
+pub fn is_ini(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+    name.ends_with(".ini") || name.ends_with(".ini.bak")
+}
+
+/// Every mod ini under `dir`, including disabled ones, in a stable order.
+pub fn ini_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_ini_files(dir, &mut files);
+    files.sort();
+    files
+}
+
+/// The ini files 3DMigoto loads for a mod, or for a disabled mod the ones it
+/// would load once enabled.
+pub fn active_ini_files(dir: &Path) -> Vec<PathBuf> {
+    let files = ini_files(dir);
+    let live: Vec<PathBuf> = files.iter()
+        .filter(|path| !is_disabled_ini(path))
+        .cloned()
+        .collect();
+
+    if live.is_empty() { files } else { live }
+}
+
+fn collect_ini_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_ini_files(&path, files);
+        } else if is_ini(&path) && !path.file_name().is_some_and(|n| n.eq_ignore_ascii_case("desktop.ini")) {
+            files.push(path);
+        }
+    }
+}
+
+pub fn inspect(name: &str, mod_path: &Path) -> Result<ModInspection, String> {
+    if !mod_path.is_dir() {
+        return Err(format!("Mod folder does not exist: {}", mod_path.display()));
+    }
+
+    let mut inspection = ModInspection {
+        name: name.to_string(),
+        files: vec![],
+        hashes: vec![],
+        keybindings: vec![],
+        namespaces: vec![],
+    };
+
+    for path in ini_files(mod_path) {
+        let ini = parse_file(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let file = path.strip_prefix(mod_path).unwrap_or(&path).to_string_lossy().to_string();
+
+        inspection.hashes.extend(ini.hashes(&file));
+        inspection.keybindings.extend(ini.key_bindings(&file));
+
+        if let Some(namespace) = &ini.namespace {
+            if !inspection.namespaces.contains(namespace) {
+                inspection.namespaces.push(namespace.clone());
+            }
+        }
+
+        inspection.files.push(IniSummary {
+            disabled: is_disabled_ini(&path),
+            namespace: ini.namespace,
+            sections: ini.sections.len(),
+            file,
+        });
+    }
+
+    Ok(inspection)
+}
+
+#[tauri::command]
+pub fn inspect_mod(root: String, name: String) -> Result<ModInspection, String> {
+    inspect(&name, &paths::mod_path(&root, &name)?)
+}
+
+/// Replaces the value of the `key = value` entry on `line` (1-based), keeping
+/// the indentation, spacing around `=` and line ending as they were.
+pub fn replace_value(content: &str, line: usize, value: &str) -> Option<String> {
+    let mut output = String::with_capacity(content.len() + value.len());
+    let mut found = false;
+
+    for (i, raw) in content.split_inclusive('\n').enumerate() {
+        if i + 1 != line {
+            output.push_str(raw);
+            continue;
+        }
+
+        let body = raw.trim_end_matches(['\r', '\n']);
+        let ending = &raw[body.len()..];
+        let eq = body.find('=')?;
+        let spacing = body[eq + 1..].len() - body[eq + 1..].trim_start().len();
+
+        output.push_str(&body[..eq + 1 + spacing]);
+        output.push_str(value);
+        output.push_str(ending);
+        found = true;
+    }
+
+    found.then_some(output)
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".orig");
+    PathBuf::from(name)
+}
+
+/// Rewrites the first `key =` line of `[section]` in a mod's ini files. The
+/// untouched file is kept next to it as `.orig` the first time it is edited;
+/// neither 3DMigoto nor the .ini.bak strategy pick that name up.
+#[tauri::command]
+pub fn rebind_mod_key(
+    root: String,
+    name: String,
+    section: String,
+    new_key: String,
+    file: Option<String>,
+    watcher: tauri::State<'_, ModWatcher>
+) -> Result<KeyBinding, String> {
+    let _pause = watcher.pause();
+    rebind(&name, &paths::mod_path(&root, &name)?, &section, &new_key, file.as_deref())
+}
+
+// Without a `file`, the first ini file with a matching section is edited.
+fn rebind(name: &str, mod_path: &Path, section: &str, new_key: &str, file: Option<&str>) -> Result<KeyBinding, String> {
+    let new_key = new_key.trim();
+    if new_key.is_empty() || new_key.contains(['\r', '\n', '[', ']', ';']) {
+        return Err(format!("Invalid key: {}", new_key));
+    }
+    if KeyCombo::parse(new_key).key.is_empty() {
+        return Err(format!("Key has no key to press, only modifiers: {}", new_key));
+    }
+
+    let candidates: Vec<PathBuf> = match file {
+        Some(file) => vec![paths::contained(mod_path, file)?],
+        None => ini_files(mod_path),
+    };
+
+    for path in candidates {
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let ini = parse(&content);
+
+        let entry = match ini.sections.iter()
+            .find(|s| s.kind == SectionKind::Key && s.name.eq_ignore_ascii_case(section))
+            .and_then(|s| s.get_all("key").next())
+        {
+            Some(entry) => entry,
+            None => continue,
+        };
+
+        let updated = replace_value(&content, entry.line, new_key)
+            .ok_or_else(|| format!("Failed to rewrite line {} of {}", entry.line, path.display()))?;
+
+        let backup = backup_path(&path);
+        if !backup.exists() {
+            fs::copy(&path, &backup)
+                .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+        }
+
+        storage::write_atomic(&path, updated.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+        let relative = path.strip_prefix(mod_path).unwrap_or(&path).to_string_lossy().to_string();
+        return parse(&updated).key_bindings(&relative)
+            .into_iter()
+            .find(|b| b.line == entry.line)
+            .ok_or_else(|| format!("Failed to read back the new binding in {}", path.display()));
+    }
+
+    Err(format!("No [{}] section with a key was found in {}", section, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("wwmm-migoto-{}-{}", label, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const SAMPLE: &str = "\u{feff}; Outfit swap for Example\n\
+namespace = Example\\Outfit\n\
+\n\
+[Constants]\n\
+global persist $swap = 0\n\
+global $active = 1\n\
+\n\
+[KeyCycleOutfit]\n\
+; cycles the outfit\n\
+condition = $active == 1\n\
+key = no_modifiers VK_F1\n\
+key = ctrl shift 7\n\
+type = cycle\n\
+$swap = 0,1,2\n\
+\n\
+[TextureOverrideBody]\n\
+hash = ABCDEF12\n\
+match_first_index = 0\n\
+if $swap == 1\n\
+  ib = ResourceBodyIB\n\
+endif\n";
+
+    #[test]
+    fn parses_sections_and_entries() {
+        let ini = parse(SAMPLE);
+
+        assert_eq!(ini.namespace.as_deref(), Some("Example\\Outfit"));
+        let names: Vec<&str> = ini.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Constants", "KeyCycleOutfit", "TextureOverrideBody"]);
+        let kinds: Vec<SectionKind> = ini.sections.iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, [SectionKind::Constants, SectionKind::Key, SectionKind::TextureOverride]);
+        assert_eq!(ini.sections[1].line, 8);
+
+        // Comments are skipped, and lines keep their place in the file
+        let key = &ini.sections[1];
+        assert_eq!(key.entries.len(), 5);
+        assert_eq!(key.get("KEY"), Some("no_modifiers VK_F1"));
+        assert_eq!(key.get_all("key").map(|e| e.line).collect::<Vec<_>>(), [11, 12]);
+        assert_eq!(key.get("$swap"), Some("0,1,2"));
+
+        // Flow control without `=` is kept as a bare key
+        let body = &ini.sections[2];
+        assert_eq!(body.get("hash"), Some("ABCDEF12"));
+        assert_eq!(body.get("endif"), Some(""));
+    }
+
+    #[test]
+    fn parses_crlf_and_malformed_lines() {
+        let ini = parse("namespace=Mod\r\n[Broken\r\nkey = F2\r\n[ Key.Toggle ]\r\n=\r\nkey=VK_F3\r\n");
+
+        assert_eq!(ini.namespace.as_deref(), Some("Mod"));
+        // An unclosed header doesn't open a section
+        assert_eq!(ini.sections.len(), 1);
+        let section = &ini.sections[0];
+        assert_eq!(section.name, "Key.Toggle");
+        assert_eq!(section.kind, SectionKind::Key);
+        assert_eq!(section.entries[0].key, "");
+        assert_eq!(section.get("key"), Some("VK_F3"));
+    }
+
+    #[test]
+    fn ignores_entries_before_the_first_section() {
+        let ini = parse("key = F1\n$var = 1\n[Present]\nrun = CommandListA\n");
+
+        assert_eq!(ini.namespace, None);
+        assert_eq!(ini.sections.len(), 1);
+        assert_eq!(ini.sections[0].kind, SectionKind::Present);
+        assert_eq!(ini.sections[0].entries.len(), 1);
+    }
+
+    #[test]
+    fn reads_key_bindings_and_variables() {
+        let ini = parse(SAMPLE);
+
+        let bindings = ini.key_bindings("mod.ini");
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(bindings[0].description, "Cycle Outfit");
+        assert_eq!(bindings[0].binding_type.as_deref(), Some("cycle"));
+        assert_eq!(bindings[0].variables, ["$swap"]);
+        assert_eq!(bindings[1].combo.label, "Ctrl+Shift+7");
+
+        let variables = ini.persistent_variables("mod.ini");
+        assert_eq!(variables.len(), 1);
+        assert_eq!(variables[0].name, "$swap");
+        assert_eq!(variables[0].default, "0");
+
+        let hashes = ini.hashes("mod.ini");
+        assert_eq!(hashes[0].hash, "abcdef12");
+        assert_eq!(hashes[0].match_first_index.as_deref(), Some("0"));
+    }
+
+    #[test]
+    fn parses_key_combos() {
+        let combo = KeyCombo::parse("no_modifiers VK_F1");
+        assert_eq!(combo.key, "F1");
+        assert_eq!(combo.ctrl, Modifier::Forbidden);
+        assert_eq!(combo.windows, Modifier::Forbidden);
+        assert_eq!(combo.label, "F1");
+
+        let combo = KeyCombo::parse("LCtrl no_alt  rshift x");
+        assert_eq!(combo.key, "X");
+        assert_eq!(combo.ctrl, Modifier::Required);
+        assert_eq!(combo.alt, Modifier::Forbidden);
+        assert_eq!(combo.shift, Modifier::Required);
+        assert_eq!(combo.windows, Modifier::Any);
+        assert_eq!(combo.label, "Ctrl+Shift+X");
+
+        let combo = KeyCombo::parse("ctrl alt");
+        assert!(combo.key.is_empty());
+        assert_eq!(combo.label, "Ctrl+Alt+");
+    }
+
+    #[test]
+    fn detects_overlapping_combos() {
+        let plain = KeyCombo::parse("VK_F1");
+        let bare = KeyCombo::parse("no_modifiers F1");
+        let ctrl = KeyCombo::parse("ctrl F1");
+
+        assert!(plain.overlaps(&bare));
+        assert!(plain.overlaps(&ctrl));
+        assert!(!bare.overlaps(&ctrl));
+        assert!(!KeyCombo::parse("ctrl").overlaps(&KeyCombo::parse("ctrl")));
+    }
+
+    #[test]
+    fn replaces_values_in_place() {
+        let crlf = "[KeyToggle]\r\nkey = F1\r\ntype = toggle\r\n";
+        assert_eq!(
+            replace_value(crlf, 2, "ctrl F2").as_deref(),
+            Some("[KeyToggle]\r\nkey = ctrl F2\r\ntype = toggle\r\n")
+        );
+
+        let spaced = "[KeyToggle]\n  key\t=   VK_F1\nback=F3";
+        assert_eq!(
+            replace_value(spaced, 2, "F2").as_deref(),
+            Some("[KeyToggle]\n  key\t=   F2\nback=F3")
+        );
+        assert_eq!(replace_value(spaced, 3, "F4").as_deref(), Some("[KeyToggle]\n  key\t=   VK_F1\nback=F4"));
+
+        // Lines without a value and lines past the end are refused
+        assert_eq!(replace_value(spaced, 1, "F2"), None);
+        assert_eq!(replace_value(spaced, 4, "F2"), None);
+    }
+
+    #[test]
+    fn rebinds_the_file_with_the_section() {
+        let dir = TempDir::new("rebind");
+        let other = "[Constants]\nglobal $x = 0\n";
+        let original = "; toggle\r\n[KeyToggle]\r\nkey = F1\r\n$x = 0,1\r\n";
+        fs::create_dir_all(dir.0.join("sub")).unwrap();
+        fs::write(dir.0.join("a.ini"), other).unwrap();
+        fs::write(dir.0.join("sub").join("b.ini"), original).unwrap();
+
+        let binding = rebind("Mod", &dir.0, "keytoggle", " no_modifiers F5 ", None).unwrap();
+
+        assert_eq!(Path::new(&binding.file), Path::new("sub").join("b.ini"));
+        assert_eq!(binding.line, 3);
+        assert_eq!(binding.combo.label, "F5");
+        assert_eq!(
+            fs::read_to_string(dir.0.join("sub").join("b.ini")).unwrap(),
+            "; toggle\r\n[KeyToggle]\r\nkey = no_modifiers F5\r\n$x = 0,1\r\n"
+        );
+        assert_eq!(fs::read_to_string(dir.0.join("sub").join("b.ini.orig")).unwrap(), original);
+        assert_eq!(fs::read_to_string(dir.0.join("a.ini")).unwrap(), other);
+        assert!(!dir.0.join("a.ini.orig").exists());
+
+        // The backup keeps the file as shipped, not as last rebound
+        rebind("Mod", &dir.0, "KeyToggle", "F6", Some("sub/b.ini")).unwrap();
+        assert_eq!(fs::read_to_string(dir.0.join("sub").join("b.ini.orig")).unwrap(), original);
+
+        let missing = rebind("Mod", &dir.0, "KeyToggle", "F7", Some("a.ini")).unwrap_err();
+        assert!(missing.contains("No [KeyToggle] section"), "{}", missing);
+    }
+
+    #[test]
+    fn refuses_keys_without_a_key_to_press() {
+        let dir = TempDir::new("modifiers");
+        let original = "[KeyToggle]\nkey = F1\n";
+        fs::write(dir.0.join("mod.ini"), original).unwrap();
+
+        for key in ["ctrl shift", "no_modifiers", "", "F1 ; comment", "F1\nkey = F2"] {
+            assert!(rebind("Mod", &dir.0, "KeyToggle", key, None).is_err(), "{:?} was accepted", key);
+        }
+        assert_eq!(fs::read_to_string(dir.0.join("mod.ini")).unwrap(), original);
+        assert!(!dir.0.join("mod.ini.orig").exists());
+    }
+}
@@ -0,0 +1,226 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::strategy::ModContext;
+use crate::{load_game_db, load_games, Database};
+
+/// Rejects anything but a plain folder or file name, so joining it onto a
+/// directory can only ever name an entry of that directory.
+pub fn check_name(name: &str) -> Result<(), String> {
+    let mut components = Path::new(name).components();
+
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(part)), None) if part == name && !name.contains(['/', '\\']) => Ok(()),
+        _ => Err(format!("Invalid mod name: {:?}", name)),
+    }
+}
+
+// Follows symlinks where the path exists; a path that doesn't exist yet can't
+// be a link, so it is compared as written.
+fn real_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Joins a relative path of possibly several components onto `base`, refusing
+/// `..`, absolute paths and anything that resolves outside `base` through a
+/// symlink.
+pub fn contained(base: &Path, relative: &str) -> Result<PathBuf, String> {
+    let mut path = base.to_path_buf();
+    for component in Path::new(relative).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return Err(format!("Path escapes {}: {}", base.display(), relative)),
+        }
+    }
+
+    // A file that doesn't exist yet is created wherever its nearest existing
+    // parent really is
+    let existing = path.ancestors()
+        .take_while(|ancestor| ancestor.starts_with(base))
+        .find(|ancestor| fs::symlink_metadata(ancestor).is_ok());
+    if existing.is_some_and(|ancestor| !real_path(ancestor).starts_with(real_path(base))) {
+        return Err(format!("Path escapes {}: {}", base.display(), relative));
+    }
+
+    Ok(path)
+}
+
+/// Every folder mods of a game live in: the game's mod folder, its disabled
+/// folder and its library.
+pub fn game_roots(db: &Database) -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if db.root_folder.is_empty() {
+        return roots;
+    }
+
+    let root = PathBuf::from(&db.root_folder);
+    if !db.disabled_folder.is_empty() {
+        roots.push(root.join(&db.disabled_folder));
+    }
+    if !db.library_folder.is_empty() {
+        roots.push(PathBuf::from(&db.library_folder));
+    }
+    roots.push(root);
+
+    roots
+}
+
+// The folders of every configured game, for callers that don't say which
+// game they work on. A game whose database can't be read is skipped, so it
+// can't lock the others out.
+fn managed_roots() -> Result<Vec<PathBuf>, String> {
+    Ok(load_games()?
+        .iter()
+        .filter_map(|game| load_game_db(game.id).ok())
+        .flat_map(|db| game_roots(&db))
+        .collect())
+}
+
+pub fn check_root(root: &Path, managed: &[PathBuf]) -> Result<(), String> {
+    let real = real_path(root);
+
+    if managed.iter().any(|dir| real_path(dir) == real) {
+        Ok(())
+    } else {
+        Err(format!("Not a mod folder of any game: {}", root.display()))
+    }
+}
+
+fn entry_in(root: &str, name: &str, managed: &[PathBuf]) -> Result<PathBuf, String> {
+    check_name(name)?;
+    let root = Path::new(root);
+    check_root(root, managed)?;
+
+    Ok(root.join(name))
+}
+
+/// The directory entry `name` inside `root`, for operations on the entry
+/// itself such as renaming it or replacing a link. `root` must be a folder
+/// of a configured game.
+pub fn entry_path(root: &str, name: &str) -> Result<PathBuf, String> {
+    entry_in(root, name, &managed_roots()?)
+}
+
+/// The mod folder `name` inside `root`, for reading, writing or deleting its
+/// contents. Unlike `entry_path`, a link pointing out of `root` is refused.
+pub fn mod_path(root: &str, name: &str) -> Result<PathBuf, String> {
+    let path = entry_path(root, name)?;
+    contained(Path::new(root), name)?;
+
+    Ok(path)
+}
+
+/// Like `entry_path`, for a caller working on the game of `db`: `root` must
+/// be one of that game's folders.
+pub fn game_entry_path(db: &Database, root: &str, name: &str) -> Result<PathBuf, String> {
+    entry_in(root, name, &game_roots(db))
+}
+
+/// Like `mod_path`, for a caller working on the game of `db`.
+pub fn game_mod_path(db: &Database, root: &str, name: &str) -> Result<PathBuf, String> {
+    let path = game_entry_path(db, root, name)?;
+    contained(Path::new(root), name)?;
+
+    Ok(path)
+}
+
+/// Checks the folders a caller passed in for a toggle: the name, and the
+/// mod, disabled and library folders, which must all belong to a game.
+pub fn check_context(ctx: &ModContext) -> Result<(), String> {
+    check_name(ctx.name)?;
+    let managed = managed_roots()?;
+
+    check_root(ctx.root, &managed)?;
+    check_root(&ctx.disabled_path(), &managed)?;
+    if !ctx.library_folder.is_empty() {
+        check_root(Path::new(ctx.library_folder), &managed)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("wwmm-paths-{}-{}", label, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn accepts_plain_names() {
+        assert!(check_name("Some Mod").is_ok());
+        assert!(check_name("Mod.v2 - Variant A").is_ok());
+    }
+
+    #[test]
+    fn rejects_traversal_names() {
+        for name in ["..", ".", "", "../Other", "Mods/../..", "a/b", "a\\b", "..\\.."] {
+            assert!(check_name(name).is_err(), "{:?} was accepted", name);
+        }
+    }
+
+    #[test]
+    fn rejects_absolute_names() {
+        assert!(check_name("/etc").is_err());
+        assert!(check_name("\\Windows").is_err());
+        assert!(check_name("C:\\Windows").is_err());
+    }
+
+    #[test]
+    fn contains_relative_paths() {
+        let dir = TempDir::new("relative");
+
+        assert_eq!(contained(&dir.0, "sub/mod.ini").unwrap(), dir.0.join("sub").join("mod.ini"));
+        assert_eq!(contained(&dir.0, "./mod.ini").unwrap(), dir.0.join("mod.ini"));
+        assert!(contained(&dir.0, "../mod.ini").is_err());
+        assert!(contained(&dir.0, "sub/../../mod.ini").is_err());
+        assert!(contained(&dir.0, "/etc/passwd").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlink_escapes() {
+        let dir = TempDir::new("symlink");
+        let root = dir.0.join("Mods");
+        let outside = dir.0.join("outside");
+        fs::create_dir_all(root.join("Real")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("Escape")).unwrap();
+        std::os::unix::fs::symlink(root.join("Real"), root.join("Inside")).unwrap();
+
+        assert!(contained(&root, "Real").is_ok());
+        assert!(contained(&root, "Inside").is_ok());
+        assert!(contained(&root, "Escape").is_err());
+        assert!(contained(&root, "Escape/notes.txt").is_err());
+    }
+
+    #[test]
+    fn accepts_only_managed_roots() {
+        let dir = TempDir::new("roots");
+        let root = dir.0.join("Mods");
+        fs::create_dir_all(&root).unwrap();
+        let managed = vec![root.clone(), dir.0.join("Library")];
+
+        assert!(check_root(&root, &managed).is_ok());
+        assert!(check_root(&root.join("."), &managed).is_ok());
+        assert!(check_root(&dir.0.join("Library"), &managed).is_ok());
+        assert!(check_root(&dir.0, &managed).is_err());
+        assert!(check_root(&root.join(".."), &managed).is_err());
+        assert!(check_root(Path::new("/"), &managed).is_err());
+    }
+}
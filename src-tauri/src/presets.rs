@@ -0,0 +1,325 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::migoto::{self, PersistentVariable, SectionKind};
+use crate::{load_game_db, storage, write_game_db, Database, VariablePreset, VariableValue};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ModVariable {
+    pub file: String,
+    pub name: String,
+    pub default: String,
+    // Saved by 3DMigoto once the variable has been changed in game
+    pub value: Option<String>,
+}
+
+// 3DMigoto keeps d3dx_user.ini in its own folder, which holds the Mods folder.
+fn user_ini_path(db: &Database) -> Result<PathBuf, String> {
+    Path::new(&db.root_folder)
+        .parent()
+        .map(|dir| dir.join("d3dx_user.ini"))
+        .ok_or_else(|| format!("Cannot find the 3DMigoto folder above {}", db.root_folder))
+}
+
+// Persisted variables are stored under the mod's namespace, or under the
+// ini's path relative to the 3DMigoto folder, always lowercased:
+// `$\mods\somemod\somemod.ini\swapvar = 1`.
+fn persisted_key(db: &Database, mod_name: &str, namespace: Option<&str>, variable: &PersistentVariable) -> String {
+    let name = variable.name.trim_start_matches('$');
+    let key = match namespace {
+        Some(namespace) => format!("$\\{}\\{}", namespace, name),
+        None => {
+            let mods_dir = Path::new(&db.root_folder).file_name().unwrap_or_default().to_string_lossy();
+            format!("$\\{}\\{}\\{}\\{}", mods_dir, mod_name, variable.file.replace('/', "\\"), name)
+        }
+    };
+    key.to_lowercase()
+}
+
+// Every persisted variable a mod declares, with the key it is saved under.
+fn declared_variables(db: &Database, mod_name: &str) -> Result<Vec<(String, PersistentVariable)>, String> {
+    let mod_path = db.mod_folder(mod_name)
+        .ok_or_else(|| format!("Mod folder not found: {}", mod_name))?;
+
+    let mut variables = Vec::new();
+    for path in migoto::active_ini_files(&mod_path) {
+        let ini = migoto::parse_file(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let file = path.strip_prefix(&mod_path).unwrap_or(&path).to_string_lossy().to_string();
+
+        for variable in ini.persistent_variables(&file) {
+            variables.push((persisted_key(db, mod_name, ini.namespace.as_deref(), &variable), variable));
+        }
+    }
+
+    Ok(variables)
+}
+
+fn read_user_values(path: &Path) -> Result<HashMap<String, String>, String> {
+    let content = match fs::read(path) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    Ok(migoto::parse(&content).sections.iter()
+        .filter(|s| s.kind == SectionKind::Constants)
+        .flat_map(|s| s.entries.iter())
+        .map(|e| (e.key.to_lowercase(), e.value.clone()))
+        .collect())
+}
+
+fn insert_lines_after(content: &str, line: usize, lines: &[String], newline: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+
+    for (i, raw) in content.split_inclusive('\n').enumerate() {
+        output.push_str(raw);
+
+        if i + 1 == line {
+            if !raw.ends_with('\n') {
+                output.push_str(newline);
+            }
+            for new_line in lines {
+                output.push_str(new_line);
+                output.push_str(newline);
+            }
+        }
+    }
+
+    output
+}
+
+// Updates entries in place and adds missing ones to `[Constants]`, leaving
+// everything else in the file untouched.
+fn write_user_values(path: &Path, values: &[(String, String)]) -> Result<(), String> {
+    let mut content = match fs::read(path) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+
+    let mut missing: Vec<String> = Vec::new();
+    for (key, value) in values {
+        let existing = migoto::parse(&content).sections.iter()
+            .filter(|s| s.kind == SectionKind::Constants)
+            .flat_map(|s| s.entries.iter())
+            .find(|e| e.key.eq_ignore_ascii_case(key))
+            .map(|e| e.line);
+
+        match existing {
+            Some(line) => {
+                content = migoto::replace_value(&content, line, value)
+                    .ok_or_else(|| format!("Failed to update {} in {}", key, path.display()))?;
+            }
+            None => missing.push(format!("{} = {}", key, value)),
+        }
+    }
+
+    if !missing.is_empty() {
+        let ini = migoto::parse(&content);
+        match ini.sections.iter().find(|s| s.kind == SectionKind::Constants) {
+            Some(section) => {
+                let after = section.entries.last().map(|e| e.line).unwrap_or(section.line);
+                content = insert_lines_after(&content, after, &missing, newline);
+            }
+            None => {
+                if !content.is_empty() && !content.ends_with('\n') {
+                    content.push_str(newline);
+                }
+                content.push_str("[Constants]");
+                content.push_str(newline);
+                for line in &missing {
+                    content.push_str(line);
+                    content.push_str(newline);
+                }
+            }
+        }
+    }
+
+    storage::write_atomic(path, content.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Current value of every persisted variable of a mod, falling back to the
+/// declared default for those 3DMigoto has not saved yet.
+pub fn capture_values(db: &Database, mod_name: &str) -> Result<Vec<VariableValue>, String> {
+    let saved = read_user_values(&user_ini_path(db)?)?;
+
+    Ok(declared_variables(db, mod_name)?
+        .into_iter()
+        .map(|(key, variable)| VariableValue {
+            value: saved.get(&key).cloned().unwrap_or(variable.default),
+            file: variable.file,
+            name: variable.name,
+        })
+        .collect())
+}
+
+pub fn write_values(db: &Database, mod_name: &str, values: &[VariableValue]) -> Result<(), String> {
+    let declared = declared_variables(db, mod_name)?;
+
+    let mut updates = Vec::new();
+    for value in values {
+        if value.value.trim().is_empty() || value.value.contains(['\r', '\n']) {
+            return Err(format!("Invalid value for {}: {}", value.name, value.value));
+        }
+
+        let (key, _) = declared.iter()
+            .find(|(_, v)| v.file == value.file && v.name.eq_ignore_ascii_case(&value.name))
+            .ok_or_else(|| format!("{} does not declare a persisted {} in {}", mod_name, value.name, value.file))?;
+        updates.push((key.clone(), value.value.trim().to_string()));
+    }
+
+    if updates.is_empty() {
+        return Ok(());
+    }
+    write_user_values(&user_ini_path(db)?, &updates)
+}
+
+/// Adds `preset` to the mod with `mod_id`, replacing one with the same name.
+pub fn store_preset(db: &mut Database, mod_id: i64, preset: VariablePreset) {
+    if let Some(m) = db.mods.iter_mut().find(|m| m.id == mod_id) {
+        m.presets.retain(|p| p.name != preset.name);
+        m.presets.push(preset);
+    }
+}
+
+pub fn apply_preset(db: &Database, mod_name: &str, preset: &str) -> Result<(), String> {
+    let values = db.mods.iter()
+        .find(|m| m.name == mod_name)
+        .and_then(|m| m.presets.iter().find(|p| p.name == preset))
+        .map(|p| p.values.clone())
+        .ok_or_else(|| format!("{} has no preset named {}", mod_name, preset))?;
+
+    write_values(db, mod_name, &values)
+}
+
+#[tauri::command]
+pub fn get_mod_variables(game_id: i64, name: String) -> Result<Vec<ModVariable>, String> {
+    let db = load_game_db(game_id)?;
+    let saved = read_user_values(&user_ini_path(&db)?)?;
+
+    Ok(declared_variables(&db, &name)?
+        .into_iter()
+        .map(|(key, variable)| ModVariable {
+            value: saved.get(&key).cloned(),
+            file: variable.file,
+            name: variable.name,
+            default: variable.default,
+        })
+        .collect())
+}
+
+/// 3DMigoto only reads d3dx_user.ini on launch or reload, and rewrites it
+/// from memory, so values set while the game runs need a reload (F10).
+#[tauri::command]
+pub fn set_mod_variables(game_id: i64, name: String, values: Vec<VariableValue>) -> Result<(), String> {
+    let db = load_game_db(game_id)?;
+    write_values(&db, &name, &values)
+}
+
+#[tauri::command]
+pub fn save_mod_preset(game_id: i64, name: String, preset: String) -> Result<(), String> {
+    let mut db = load_game_db(game_id)?;
+    let mod_id = db.mods.iter()
+        .find(|m| m.name == name)
+        .map(|m| m.id)
+        .ok_or_else(|| format!("Mod not found: {}", name))?;
+
+    let values = capture_values(&db, &name)?;
+    if values.is_empty() {
+        return Err(format!("{} has no persisted variables", name));
+    }
+
+    store_preset(&mut db, mod_id, VariablePreset { name: preset, values });
+    write_game_db(game_id, db)
+}
+
+#[tauri::command]
+pub fn apply_mod_preset(game_id: i64, name: String, preset: String) -> Result<(), String> {
+    let db = load_game_db(game_id)?;
+    apply_preset(&db, &name, &preset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("wwmm-presets-{}-{}", label, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn values(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    // Writes `content` as d3dx_user.ini, applies `updates` and reads it back.
+    fn rewrite(label: &str, content: Option<&str>, updates: &[(&str, &str)]) -> String {
+        let dir = TempDir::new(label);
+        let path = dir.0.join("d3dx_user.ini");
+        if let Some(content) = content {
+            fs::write(&path, content).unwrap();
+        }
+
+        write_user_values(&path, &values(updates)).unwrap();
+        fs::read_to_string(&path).unwrap()
+    }
+
+    #[test]
+    fn updates_existing_values() {
+        let content = "; saved by 3DMigoto\r\n[Constants]\r\n$\\mods\\a\\a.ini\\swap = 0\r\n$\\other\\x  =  3\r\n";
+
+        assert_eq!(
+            rewrite("update", Some(content), &[("$\\Mods\\A\\a.ini\\swap", "2"), ("$\\other\\x", "1")]),
+            "; saved by 3DMigoto\r\n[Constants]\r\n$\\mods\\a\\a.ini\\swap = 2\r\n$\\other\\x  =  1\r\n"
+        );
+    }
+
+    #[test]
+    fn inserts_into_the_existing_section() {
+        let content = "[Present]\nrun = CommandListA\n[Constants]\n$\\a\\x = 1\n\n; keep me\n[Other]\n$\\b\\y = 5\n";
+
+        assert_eq!(
+            rewrite("insert", Some(content), &[("$\\b\\y", "7"), ("$\\a\\x", "4")]),
+            "[Present]\nrun = CommandListA\n[Constants]\n$\\a\\x = 4\n$\\b\\y = 7\n\n; keep me\n[Other]\n$\\b\\y = 5\n"
+        );
+    }
+
+    #[test]
+    fn creates_a_missing_section() {
+        assert_eq!(
+            rewrite("append", Some("; header\r\n[Present]\r\nrun = CommandListA"), &[("$\\a\\x", "1")]),
+            "; header\r\n[Present]\r\nrun = CommandListA\r\n[Constants]\r\n$\\a\\x = 1\r\n"
+        );
+        assert_eq!(
+            rewrite("missing", None, &[("$\\a\\x", "1"), ("$\\a\\y", "2")]),
+            "[Constants]\n$\\a\\x = 1\n$\\a\\y = 2\n"
+        );
+    }
+
+    #[test]
+    fn inserts_lines_after_a_line() {
+        let lines = vec![String::from("b = 2"), String::from("c = 3")];
+
+        assert_eq!(insert_lines_after("a = 1\nd = 4\n", 1, &lines, "\n"), "a = 1\nb = 2\nc = 3\nd = 4\n");
+        assert_eq!(insert_lines_after("x\r\na = 1", 2, &lines, "\r\n"), "x\r\na = 1\r\nb = 2\r\nc = 3\r\n");
+        assert_eq!(insert_lines_after("a = 1\n", 5, &lines, "\n"), "a = 1\n");
+    }
+}
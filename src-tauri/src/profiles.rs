@@ -0,0 +1,313 @@
+use serde::Serialize;
+use tauri::Emitter;
+
+use crate::exclusive::{self, ModChange};
+use crate::history::Change;
+use crate::strategy::{self, ModStrategy};
+use crate::watcher::ModWatcher;
+use crate::{apply_toggle, history, load_game_db, paths, presets, write_game_db, Database, Profile, ProfilePreset, VariablePreset};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProfileProgress {
+    pub game_id: i64,
+    pub profile: String,
+    pub mod_name: String,
+    pub enable: bool,
+    pub done: usize,
+    pub total: usize,
+    pub rolling_back: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProfileResult {
+    pub enabled: Vec<String>,
+    pub disabled: Vec<String>,
+}
+
+/// Enables exactly the mods in `profile`, disables every other mod and
+/// restores the variable presets the profile saved. A profile holding two
+/// mods that share a mutually exclusive tag is refused before anything
+/// changes. If a toggle fails, the mods already changed are toggled back
+/// before returning.
+#[tauri::command]
+pub async fn apply_profile(game_id: i64, profile: String, window: tauri::Window, watcher: tauri::State<'_, ModWatcher>) -> Result<ProfileResult, String> {
+    let _pause = watcher.pause();
+    let mut db = load_game_db(game_id)?;
+    let strategy = strategy::resolve(&db.mod_strategy)?;
+
+    let (mod_ids, restores) = db.profiles.iter()
+        .find(|p| p.name == profile)
+        .map(|p| (p.mod_ids.clone(), p.presets.clone()))
+        .ok_or_else(|| format!("Profile not found: {}", profile))?;
+
+    let mut emit = |mod_name: &str, enable: bool, done: usize, total: usize, rolling_back: bool| {
+        let _ = window.emit("profile-progress", ProfileProgress {
+            game_id,
+            profile: profile.clone(),
+            mod_name: mod_name.to_string(),
+            enable,
+            done,
+            total,
+            rolling_back,
+        });
+    };
+
+    let changes = switch_mods(&mut db, strategy, &mod_ids, &mut emit)?;
+
+    // Mods are in place now, so their ini files can be found
+    let mut preset_errors = Vec::new();
+    for restore in &restores {
+        if let Some(m) = db.mods.iter().find(|m| m.id == restore.mod_id && m.enabled) {
+            if let Err(e) = presets::apply_preset(&db, &m.name, &restore.preset) {
+                preset_errors.push(e);
+            }
+        }
+    }
+
+    write_game_db(game_id, db)?;
+
+    let (enabled, disabled): (Vec<&ModChange>, Vec<&ModChange>) = changes.iter().partition(|c| c.enabled);
+    let result = ProfileResult {
+        enabled: enabled.into_iter().map(|c| c.name.clone()).collect(),
+        disabled: disabled.into_iter().map(|c| c.name.clone()).collect(),
+    };
+    let total = changes.len();
+    let recorded = if changes.is_empty() {
+        Ok(())
+    } else {
+        history::record(game_id, format!("Apply profile {}", profile), Change::Toggle { mods: changes })
+    };
+
+    emit("", false, total, total, false);
+
+    if !preset_errors.is_empty() {
+        return Err(format!("Mods were switched, but some variables could not be restored:\n{}", preset_errors.join("\n")));
+    }
+
+    recorded.map(|_| result)
+}
+
+// Mutually exclusive tags shared by more than one of `mod_ids`, with the
+// mods sharing each.
+fn exclusive_clashes(db: &Database, mod_ids: &[i64]) -> Vec<(String, Vec<String>)> {
+    db.tag_metadata.iter()
+        .filter(|meta| meta.mutually_exclusive)
+        .filter_map(|meta| {
+            let mods: Vec<String> = db.mods.iter()
+                .filter(|m| mod_ids.contains(&m.id) && m.tags.contains(&meta.name))
+                .map(|m| m.name.clone())
+                .collect();
+            (mods.len() > 1).then(|| (meta.name.clone(), mods))
+        })
+        .collect()
+}
+
+// Toggles the mods of `db` so that exactly `mod_ids` are enabled, reporting
+// each step to `emit`. If a toggle fails, the mods already changed are put
+// back before the error is returned.
+fn switch_mods(
+    db: &mut Database,
+    strategy: &dyn ModStrategy,
+    mod_ids: &[i64],
+    emit: &mut dyn FnMut(&str, bool, usize, usize, bool)
+) -> Result<Vec<ModChange>, String> {
+    // Enabling one mod of a clash would disable or refuse the other
+    let clashes = exclusive_clashes(db, mod_ids);
+    if !clashes.is_empty() {
+        let details: Vec<String> = clashes.iter()
+            .map(|(tag, mods)| format!("tag \"{}\" is mutually exclusive between {}", tag, mods.join(", ")))
+            .collect();
+        return Err(format!("The profile can't be applied: {}", details.join("; ")));
+    }
+
+    // Disable first so mods sharing files with the ones being enabled are
+    // out of the way
+    let mut steps: Vec<(String, bool)> = db.mods.iter()
+        .filter(|m| m.enabled && !mod_ids.contains(&m.id))
+        .map(|m| (m.name.clone(), false))
+        .collect();
+    steps.extend(db.mods.iter()
+        .filter(|m| !m.enabled && mod_ids.contains(&m.id))
+        .map(|m| (m.name.clone(), true)));
+
+    // Names come from the database, which may have been edited by hand
+    for (name, _) in &steps {
+        paths::check_name(name)?;
+    }
+
+    let total = steps.len();
+    let mut changes: Vec<ModChange> = Vec::new();
+    for (i, (name, enable)) in steps.iter().enumerate() {
+        emit(name, *enable, i, total, false);
+
+        if let Err(e) = exclusive::toggle(db, strategy, name, *enable, &mut changes, None) {
+            let mut message = format!("Failed to {} {}: {}", if *enable { "enable" } else { "disable" }, name, e);
+
+            for (j, change) in changes.iter().enumerate().rev() {
+                emit(&change.name, !change.enabled, j, total, true);
+                match apply_toggle(strategy, &db.mod_context(&change.name), !change.enabled) {
+                    Ok(()) => {
+                        if let Some(m) = db.mods.iter_mut().find(|m| m.name == change.name) {
+                            m.enabled = !change.enabled;
+                        }
+                    }
+                    Err(e) => message.push_str(&format!("\nFailed to restore {}: {}", change.name, e)),
+                }
+            }
+
+            if message.contains('\n') {
+                message.push_str("\nUse Scan Mod Folder to bring the database back in line with the mod folder.");
+            }
+            return Err(message);
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Saves the enabled mods as `name`, replacing a profile of the same name.
+/// Each enabled mod's current variable values are stored as a preset named
+/// after the profile, so applying it later restores them too.
+#[tauri::command]
+pub fn save_profile(game_id: i64, name: String) -> Result<(), String> {
+    let mut db = load_game_db(game_id)?;
+
+    let enabled: Vec<(i64, String)> = db.mods.iter()
+        .filter(|m| m.enabled)
+        .map(|m| (m.id, m.name.clone()))
+        .collect();
+
+    let mut profile = Profile {
+        name: name.clone(),
+        mod_ids: enabled.iter().map(|(id, _)| *id).collect(),
+        presets: vec![],
+    };
+
+    for (mod_id, mod_name) in &enabled {
+        // Mods without persisted variables, or whose files can't be read, have nothing to restore
+        let values = match presets::capture_values(&db, mod_name) {
+            Ok(values) if !values.is_empty() => values,
+            _ => continue,
+        };
+
+        presets::store_preset(&mut db, *mod_id, VariablePreset { name: name.clone(), values });
+        profile.presets.push(ProfilePreset { mod_id: *mod_id, preset: name.clone() });
+    }
+
+    db.profiles.retain(|p| p.name != name);
+    db.profiles.push(profile);
+    write_game_db(game_id, db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("wwmm-profiles-{}-{}", label, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    // A generic_rename game with ids from 1, where "Outfit" is a mutually
+    // exclusive tag that auto-disables.
+    fn game(dir: &Path, mods: &[(&str, bool, &[&str])]) -> Database {
+        let root = dir.join("Mods");
+        fs::create_dir_all(root.join("_Disabled")).unwrap();
+
+        let records: Vec<serde_json::Value> = mods.iter()
+            .enumerate()
+            .map(|(i, (name, enabled, tags))| {
+                let folder = if *enabled { root.join(name) } else { root.join("_Disabled").join(name) };
+                fs::create_dir_all(&folder).unwrap();
+                fs::write(folder.join("mod.ini"), "[Constants]\n").unwrap();
+
+                serde_json::json!({
+                    "id": i + 1,
+                    "name": name,
+                    "category_id": 0,
+                    "enabled": enabled,
+                    "notes": "",
+                    "preview": null,
+                    "tags": tags,
+                })
+            })
+            .collect();
+
+        serde_json::from_value(serde_json::json!({
+            "root_folder": root,
+            "disabled_folder": "_Disabled",
+            "mod_strategy": "generic_rename",
+            "categories": [],
+            "mods": records,
+            "tags": ["Outfit"],
+            "tag_metadata": [{
+                "name": "Outfit",
+                "description": "",
+                "preview": null,
+                "mutually_exclusive": true,
+                "exclusive_mode": "auto_disable",
+            }],
+            "profiles": [],
+        })).unwrap()
+    }
+
+    // Each mod's state as recorded in `db` and as found on disk.
+    fn states(db: &Database) -> Vec<(bool, bool)> {
+        let root = Path::new(&db.root_folder);
+        db.mods.iter().map(|m| (m.enabled, root.join(&m.name).exists())).collect()
+    }
+
+    fn switch(db: &mut Database, mod_ids: &[i64]) -> Result<Vec<ModChange>, String> {
+        switch_mods(db, strategy::get("generic_rename").unwrap(), mod_ids, &mut |_, _, _, _, _| {})
+    }
+
+    #[test]
+    fn enables_exactly_the_profile() {
+        let dir = TempDir::new("switch");
+        let mut db = game(&dir.0, &[("A", true, &["Outfit"]), ("B", false, &["Outfit"]), ("C", false, &[])]);
+
+        let changes = switch(&mut db, &[2, 3]).unwrap();
+
+        let changed: Vec<(&str, bool)> = changes.iter().map(|c| (c.name.as_str(), c.enabled)).collect();
+        assert_eq!(changed, [("A", false), ("B", true), ("C", true)]);
+        assert_eq!(states(&db), [(false, false), (true, true), (true, true)]);
+    }
+
+    #[test]
+    fn refuses_mods_sharing_an_exclusive_tag() {
+        let dir = TempDir::new("clash");
+        let mut db = game(&dir.0, &[("A", false, &["Outfit"]), ("B", false, &["Outfit"]), ("C", true, &[])]);
+
+        let error = switch(&mut db, &[1, 2]).unwrap_err();
+
+        assert!(error.contains("\"Outfit\" is mutually exclusive between A, B"), "{}", error);
+        assert_eq!(states(&db), [(false, false), (false, false), (true, true)]);
+    }
+
+    #[test]
+    fn failed_toggle_puts_mods_back() {
+        let dir = TempDir::new("rollback");
+        let mut db = game(&dir.0, &[("A", true, &[]), ("B", false, &[]), ("C", true, &[])]);
+        // A leftover folder in the way makes disabling C fail after A is done
+        fs::create_dir_all(dir.0.join("Mods/_Disabled/C/leftover")).unwrap();
+
+        let error = switch(&mut db, &[2]).unwrap_err();
+
+        assert!(error.starts_with("Failed to disable C"), "{}", error);
+        assert_eq!(states(&db), [(true, true), (false, false), (true, true)]);
+    }
+}
@@ -0,0 +1,143 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::strategy;
+use crate::{load_game_db, write_game_db, Mod};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ModRef {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StateMismatch {
+    pub id: i64,
+    pub name: String,
+    pub db_enabled: bool,
+    pub disk_enabled: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ScanReport {
+    pub untracked: Vec<String>,
+    pub missing: Vec<ModRef>,
+    pub state_mismatches: Vec<StateMismatch>,
+    pub applied: bool,
+}
+
+#[tauri::command]
+pub fn scan_mod_folder(game_id: i64, apply_fixes: bool) -> Result<ScanReport, String> {
+    let mut db = load_game_db(game_id)?;
+    let strategy = strategy::resolve(&db.mod_strategy)?;
+    
+    let search_dirs = strategy.search_dirs(&db.mod_context(""));
+    let disabled_path = db.mod_context("").disabled_path();
+    
+    let mut on_disk: Vec<String> = Vec::new();
+    for dir in &search_dirs {
+        for name in list_mod_folders(dir, &disabled_path)? {
+            if !on_disk.contains(&name) {
+                on_disk.push(name);
+            }
+        }
+    }
+    
+    let tracked: HashSet<&str> = db.mods.iter().map(|m| m.name.as_str()).collect();
+    let untracked: Vec<String> = on_disk.iter()
+        .filter(|name| !tracked.contains(name.as_str()))
+        .cloned()
+        .collect();
+    
+    let mut missing = Vec::new();
+    let mut state_mismatches = Vec::new();
+    
+    for m in &db.mods {
+        if !on_disk.contains(&m.name) {
+            missing.push(ModRef { id: m.id, name: m.name.clone() });
+            continue;
+        }
+        
+        let disk_enabled = strategy.is_enabled(&db.mod_context(&m.name));
+        if disk_enabled != m.enabled {
+            state_mismatches.push(StateMismatch {
+                id: m.id,
+                name: m.name.clone(),
+                db_enabled: m.enabled,
+                disk_enabled,
+            });
+        }
+    }
+    
+    let has_changes = !untracked.is_empty() || !missing.is_empty() || !state_mismatches.is_empty();
+    
+    if apply_fixes && has_changes {
+        let missing_ids: HashSet<i64> = missing.iter().map(|m| m.id).collect();
+        db.mods.retain(|m| !missing_ids.contains(&m.id));
+        
+        for mismatch in &state_mismatches {
+            if let Some(m) = db.mods.iter_mut().find(|m| m.id == mismatch.id) {
+                m.enabled = mismatch.disk_enabled;
+            }
+        }
+        
+        let root_category = db.categories.iter()
+            .find(|c| c.parent_id.is_none())
+            .map(|c| c.id)
+            .unwrap_or(1);
+        
+        for name in &untracked {
+            let id = db.mods.iter().map(|m| m.id).max().unwrap_or(0) + 1;
+            let enabled = strategy.is_enabled(&db.mod_context(name));
+            db.mods.push(Mod {
+                id,
+                name: name.clone(),
+                category_id: root_category,
+                enabled,
+                notes: String::new(),
+                preview: None,
+                tags: vec![],
+                presets: vec![],
+            });
+        }
+        
+        write_game_db(game_id, db)?;
+    }
+    
+    Ok(ScanReport {
+        untracked,
+        missing,
+        state_mismatches,
+        applied: apply_fixes && has_changes,
+    })
+}
+
+fn list_mod_folders(dir: &Path, disabled_path: &Path) -> Result<Vec<String>, String> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    
+    let mut names = Vec::new();
+    
+    for entry in fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path();
+        
+        // The disabled folder sits inside the root but is not a mod itself
+        if !path.is_dir() || path == disabled_path {
+            continue;
+        }
+        
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        
+        names.push(name);
+    }
+    
+    names.sort();
+    Ok(names)
+}
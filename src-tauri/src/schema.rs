@@ -0,0 +1,273 @@
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+use crate::{backups, storage, Database};
+
+/// Version written to every database. Bump it together with a new entry in
+/// `MIGRATIONS` whenever `Database`, `Mod` or `Category` change shape.
+pub const CURRENT_VERSION: u32 = 4;
+
+pub fn current_version() -> u32 {
+    CURRENT_VERSION
+}
+
+// `MIGRATIONS[n]` upgrades a version `n` document to version `n + 1`.
+type Migration = fn(&mut Map<String, Value>, &str);
+
+const MIGRATIONS: &[Migration] = &[
+    upgrade_unversioned,
+    add_exclusive_mode,
+    add_profiles,
+    add_variable_presets,
+];
+
+/// Reads the database at `path`, upgrading it in place if it was written
+/// by an older version. The original is snapshotted before it is replaced.
+pub fn load_database(path: &Path, default_strategy: &str) -> Result<Database, String> {
+    let value: Value = storage::read_json(path)?;
+    let version = document_version(&value);
+
+    let db = upgrade(value, default_strategy)?;
+
+    if version < CURRENT_VERSION {
+        backups::snapshot(path, true)
+            .map_err(|e| format!("Failed to back up database before upgrading: {}", e))?;
+        storage::write_json(path, &db)?;
+    }
+
+    Ok(db)
+}
+
+/// Upgrades a database document of any known version to a `Database`.
+pub fn upgrade(mut value: Value, default_strategy: &str) -> Result<Database, String> {
+    let version = document_version(&value);
+
+    if version > CURRENT_VERSION {
+        return Err(format!(
+            "Database uses schema version {}, but this version of the manager only supports up to {}",
+            version, CURRENT_VERSION
+        ));
+    }
+
+    let doc = value.as_object_mut()
+        .ok_or_else(|| "Database is not a JSON object".to_string())?;
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(doc, default_strategy);
+    }
+    doc.insert("schema_version".to_string(), json!(CURRENT_VERSION));
+
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+fn document_version(value: &Value) -> u32 {
+    value.get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as u32
+}
+
+fn is_missing_or_empty(doc: &Map<String, Value>, key: &str) -> bool {
+    match doc.get(key) {
+        None | Some(Value::Null) => true,
+        Some(Value::String(s)) => s.is_empty(),
+        Some(_) => false,
+    }
+}
+
+fn set_default(doc: &mut Map<String, Value>, key: &str, default: Value) {
+    if doc.get(key).is_none_or(Value::is_null) {
+        doc.insert(key.to_string(), default);
+    }
+}
+
+// Version 0 covers every file written before versioning existed: early
+// files lack tags, tag metadata and the strategy settings entirely, later
+// ones may carry them as empty strings or already have a library folder.
+fn upgrade_unversioned(doc: &mut Map<String, Value>, default_strategy: &str) {
+    if is_missing_or_empty(doc, "mod_strategy") {
+        doc.insert("mod_strategy".to_string(), json!(default_strategy));
+    }
+    if is_missing_or_empty(doc, "disabled_folder") {
+        doc.insert("disabled_folder".to_string(), json!("_Disabled"));
+    }
+    set_default(doc, "root_folder", json!(""));
+    set_default(doc, "library_folder", json!(""));
+    set_default(doc, "categories", json!([
+        { "id": 1, "name": "Root", "parent_id": null, "expanded": true }
+    ]));
+    set_default(doc, "mods", json!([]));
+    set_default(doc, "tag_metadata", json!([]));
+
+    if let Some(Value::Array(categories)) = doc.get_mut("categories") {
+        for category in categories.iter_mut().filter_map(Value::as_object_mut) {
+            set_default(category, "parent_id", Value::Null);
+            set_default(category, "expanded", json!(false));
+        }
+    }
+
+    if let Some(Value::Array(metadata)) = doc.get_mut("tag_metadata") {
+        for tag in metadata.iter_mut().filter_map(Value::as_object_mut) {
+            set_default(tag, "description", json!(""));
+            set_default(tag, "preview", Value::Null);
+            set_default(tag, "mutually_exclusive", json!(false));
+        }
+    }
+
+    let mut mod_tags: Vec<Value> = Vec::new();
+    if let Some(Value::Array(mods)) = doc.get_mut("mods") {
+        for m in mods.iter_mut().filter_map(Value::as_object_mut) {
+            set_default(m, "category_id", json!(1));
+            set_default(m, "enabled", json!(true));
+            set_default(m, "notes", json!(""));
+            set_default(m, "preview", Value::Null);
+            set_default(m, "tags", json!([]));
+
+            if let Some(Value::Array(tags)) = m.get("tags") {
+                for tag in tags {
+                    if !mod_tags.contains(tag) {
+                        mod_tags.push(tag.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    set_default(doc, "tags", Value::Array(mod_tags));
+}
+
+// Version 2 lets each mutually exclusive tag choose how conflicts are
+// resolved. Existing tags refuse, which changes nothing on disk behind the
+// user's back.
+fn add_exclusive_mode(doc: &mut Map<String, Value>, _default_strategy: &str) {
+    if let Some(Value::Array(metadata)) = doc.get_mut("tag_metadata") {
+        for tag in metadata.iter_mut().filter_map(Value::as_object_mut) {
+            set_default(tag, "exclusive_mode", json!("refuse"));
+        }
+    }
+}
+
+// Version 3 adds loadout profiles.
+fn add_profiles(doc: &mut Map<String, Value>, _default_strategy: &str) {
+    set_default(doc, "profiles", json!([]));
+}
+
+// Version 4 adds variable presets to mods, and lets profiles restore them.
+fn add_variable_presets(doc: &mut Map<String, Value>, _default_strategy: &str) {
+    for key in ["mods", "profiles"] {
+        if let Some(Value::Array(items)) = doc.get_mut(key) {
+            for item in items.iter_mut().filter_map(Value::as_object_mut) {
+                set_default(item, "presets", json!([]));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExclusiveMode;
+
+    fn upgrade_fixture(content: &str, default_strategy: &str) -> Database {
+        let value: Value = serde_json::from_str(content).unwrap();
+        upgrade(value, default_strategy).unwrap()
+    }
+
+    #[test]
+    fn upgrades_unversioned_legacy_file() {
+        let db = upgrade_fixture(include_str!("../tests/fixtures/schema/v0_legacy.json"), "wuthering_waves");
+
+        assert_eq!(db.schema_version, CURRENT_VERSION);
+        assert_eq!(db.mod_strategy, "wuthering_waves");
+        assert_eq!(db.disabled_folder, "_Disabled");
+        assert_eq!(db.library_folder, "");
+        assert_eq!(db.categories.len(), 2);
+        assert!(!db.categories[1].expanded);
+        assert_eq!(db.mods.len(), 2);
+        assert_eq!(db.mods[0].notes, "");
+        assert!(db.mods[0].tags.is_empty());
+        assert!(db.tags.is_empty());
+        assert!(db.tag_metadata.is_empty());
+    }
+
+    #[test]
+    fn fills_empty_strategy_settings() {
+        let db = upgrade_fixture(include_str!("../tests/fixtures/schema/v0_empty_strategy.json"), "generic_rename");
+
+        assert_eq!(db.mod_strategy, "generic_rename");
+        assert_eq!(db.disabled_folder, "_Disabled");
+        assert_eq!(db.tags, vec!["Outfit", "Weapon"]);
+    }
+
+    #[test]
+    fn collects_tags_from_mods_when_tag_list_is_missing() {
+        let db = upgrade_fixture(include_str!("../tests/fixtures/schema/v0_mod_tags.json"), "generic_rename");
+
+        assert_eq!(db.tags, vec!["Outfit", "Hair"]);
+    }
+
+    #[test]
+    fn upgrades_unversioned_file_with_library_folder() {
+        let db = upgrade_fixture(include_str!("../tests/fixtures/schema/v0_library.json"), "generic_rename");
+
+        assert_eq!(db.schema_version, CURRENT_VERSION);
+        assert_eq!(db.mod_strategy, "link_deploy");
+        assert_eq!(db.library_folder, "D:\\ModLibrary");
+    }
+
+    #[test]
+    fn upgrades_v1_file_without_tag_metadata() {
+        let db = upgrade_fixture(include_str!("../tests/fixtures/schema/v1.json"), "generic_rename");
+
+        assert_eq!(db.schema_version, CURRENT_VERSION);
+        assert_eq!(db.mod_strategy, "wuthering_waves");
+        assert_eq!(db.tags, vec!["Outfit"]);
+        assert!(db.tag_metadata.is_empty());
+        assert!(db.mods[0].presets.is_empty());
+        assert!(db.profiles.is_empty());
+    }
+
+    #[test]
+    fn adds_exclusive_mode_to_v1_tags() {
+        let db = upgrade_fixture(include_str!("../tests/fixtures/schema/v1_exclusive_tag.json"), "generic_rename");
+
+        assert_eq!(db.schema_version, CURRENT_VERSION);
+        assert_eq!(db.tag_metadata.len(), 1);
+        assert!(db.tag_metadata[0].mutually_exclusive);
+        assert_eq!(db.tag_metadata[0].exclusive_mode, ExclusiveMode::Refuse);
+        assert!(db.profiles.is_empty());
+    }
+
+    #[test]
+    fn adds_empty_profiles_to_v2_file() {
+        let db = upgrade_fixture(include_str!("../tests/fixtures/schema/v2.json"), "generic_rename");
+
+        assert_eq!(db.schema_version, CURRENT_VERSION);
+        assert_eq!(db.tag_metadata[0].exclusive_mode, ExclusiveMode::AutoDisable);
+        assert!(db.profiles.is_empty());
+    }
+
+    #[test]
+    fn adds_presets_to_v3_mods_and_profiles() {
+        let db = upgrade_fixture(include_str!("../tests/fixtures/schema/v3.json"), "generic_rename");
+
+        assert_eq!(db.schema_version, CURRENT_VERSION);
+        assert!(db.mods[0].presets.is_empty());
+        assert_eq!(db.profiles[0].mod_ids, vec![1]);
+        assert!(db.profiles[0].presets.is_empty());
+    }
+
+    #[test]
+    fn leaves_current_file_unchanged() {
+        let content = include_str!("../tests/fixtures/schema/v4.json");
+        let db = upgrade_fixture(content, "generic_rename");
+
+        let original: Value = serde_json::from_str(content).unwrap();
+        assert_eq!(serde_json::to_value(&db).unwrap(), original);
+    }
+
+    #[test]
+    fn rejects_newer_schema_version() {
+        let value = json!({ "schema_version": CURRENT_VERSION + 1 });
+        assert!(upgrade(value, "generic_rename").is_err());
+    }
+}
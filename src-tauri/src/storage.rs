@@ -0,0 +1,230 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Replaces `path` with `contents` so that a crash leaves either the old or
+/// the new file on disk, never a truncated one.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let tmp_path = with_suffix(path, ".tmp");
+
+    {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+
+    if let Err(e) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    sync_parent_dir(path);
+    Ok(())
+}
+
+// Makes the rename itself durable. Windows has no directory handles to
+// flush, and NTFS journals the rename anyway.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) {
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) {}
+
+/// Serializes `value` to `path` atomically, keeping the previous version
+/// as a backup if it was still readable.
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| e.to_string())?;
+
+    if is_valid_json(path) {
+        let _ = fs::copy(path, with_suffix(path, ".bak"));
+    }
+
+    write_atomic(path, json.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Reads `path` as JSON. If the file is damaged (truncated or not JSON at
+/// all), the previous version or the newest snapshot that parses is
+/// restored in its place and the damaged file is kept next to it with a
+/// `.corrupt` suffix.
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| e.to_string())?;
+
+    let err = match serde_json::from_str::<T>(&content) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+
+    // A well-formed document with the wrong shape is not corruption, and
+    // restoring an older backup over it would throw away the user's data
+    if !(err.is_syntax() || err.is_eof()) {
+        return Err(err.to_string());
+    }
+
+    for backup in backup_candidates(path) {
+        let text = match fs::read_to_string(&backup) {
+            Ok(text) => text,
+            Err(_) => continue,
+        };
+
+        if let Ok(value) = serde_json::from_str::<T>(&text) {
+            let _ = fs::copy(path, with_suffix(path, ".corrupt"));
+            write_atomic(path, text.as_bytes())
+                .map_err(|e| format!("Failed to restore {} from backup: {}", path.display(), e))?;
+            return Ok(value);
+        }
+    }
+
+    Err(format!("{} is damaged and no usable backup was found: {}", path.display(), err))
+}
+
+/// Removes a JSON file together with the backup kept by `write_json`.
+pub fn remove_json(path: &Path) -> io::Result<()> {
+    let backup = with_suffix(path, ".bak");
+    if backup.exists() {
+        fs::remove_file(&backup)?;
+    }
+
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+fn backup_candidates(path: &Path) -> Vec<PathBuf> {
+    let mut candidates = vec![with_suffix(path, ".bak")];
+    candidates.extend(crate::backups::snapshot_files(path).into_iter().map(|(_, p)| p));
+    candidates
+}
+
+fn is_valid_json(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .map(|content| serde_json::from_str::<serde_json::Value>(&content).is_ok())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("wwmm-storage-{}-{}", label, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const TRUNCATED: &str = "{\"mods\": [{\"name\": \"A\"";
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn keeps_the_previous_version_as_backup() {
+        let dir = TempDir::new("write");
+        let path = dir.0.join("game-1.json");
+
+        write_json(&path, &json!({ "version": 1 })).unwrap();
+        assert!(!with_suffix(&path, ".bak").exists());
+
+        write_json(&path, &json!({ "version": 2 })).unwrap();
+        let backup: Value = read_json(&with_suffix(&path, ".bak")).unwrap();
+        assert_eq!(backup, json!({ "version": 1 }));
+
+        // A damaged file doesn't replace a good backup
+        fs::write(&path, TRUNCATED).unwrap();
+        write_json(&path, &json!({ "version": 3 })).unwrap();
+        let backup: Value = read_json(&with_suffix(&path, ".bak")).unwrap();
+        assert_eq!(backup, json!({ "version": 1 }));
+        assert!(!with_suffix(&path, ".tmp").exists());
+    }
+
+    #[test]
+    fn recovers_from_the_backup() {
+        let dir = TempDir::new("bak");
+        let path = dir.0.join("game-1.json");
+        write_json(&path, &json!({ "version": 1 })).unwrap();
+        write_json(&path, &json!({ "version": 2 })).unwrap();
+        fs::write(&path, TRUNCATED).unwrap();
+
+        let value: Value = read_json(&path).unwrap();
+
+        assert_eq!(value, json!({ "version": 1 }));
+        assert_eq!(read(&with_suffix(&path, ".corrupt")), TRUNCATED);
+        assert_eq!(serde_json::from_str::<Value>(&read(&path)).unwrap(), value);
+    }
+
+    #[test]
+    fn recovers_from_the_newest_readable_snapshot() {
+        let dir = TempDir::new("snapshot");
+        let path = dir.0.join("game-1.json");
+        let backups = dir.0.join("backups");
+        fs::create_dir_all(&backups).unwrap();
+        fs::write(backups.join("game-1-1000.json"), "{\"version\": 1}").unwrap();
+        fs::write(backups.join("game-1-2000.json"), "{\"version\": 2}").unwrap();
+        fs::write(backups.join("game-1-3000.json"), TRUNCATED).unwrap();
+        fs::write(backups.join("game-2-4000.json"), "{\"version\": 4}").unwrap();
+        fs::write(with_suffix(&path, ".bak"), "").unwrap();
+        fs::write(&path, "").unwrap();
+
+        let value: Value = read_json(&path).unwrap();
+
+        assert_eq!(value, json!({ "version": 2 }));
+        assert_eq!(read(&path), "{\"version\": 2}");
+        assert_eq!(read(&with_suffix(&path, ".corrupt")), "");
+    }
+
+    #[test]
+    fn leaves_a_well_formed_file_of_the_wrong_shape() {
+        let dir = TempDir::new("shape");
+        let path = dir.0.join("games.json");
+        write_json(&path, &json!([{ "id": 1 }])).unwrap();
+        write_json(&path, &json!({ "id": 1 })).unwrap();
+
+        assert!(read_json::<Vec<Value>>(&path).is_err());
+
+        assert_eq!(serde_json::from_str::<Value>(&read(&path)).unwrap(), json!({ "id": 1 }));
+        assert!(!with_suffix(&path, ".corrupt").exists());
+    }
+
+    #[test]
+    fn reports_damage_without_a_backup() {
+        let dir = TempDir::new("lost");
+        let path = dir.0.join("games.json");
+        fs::write(&path, TRUNCATED).unwrap();
+
+        let error = read_json::<Value>(&path).unwrap_err();
+
+        assert!(error.contains("is damaged and no usable backup was found"), "{}", error);
+        assert_eq!(read(&path), TRUNCATED);
+    }
+}
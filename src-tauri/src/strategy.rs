@@ -0,0 +1,405 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::transfer;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StrategyInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+}
+
+/// Everything a strategy needs to locate a single mod on disk.
+pub struct ModContext<'a> {
+    pub root: &'a Path,
+    pub name: &'a str,
+    pub disabled_folder: &'a str,
+    pub library_folder: &'a str,
+}
+
+impl ModContext<'_> {
+    pub fn mod_path(&self) -> PathBuf {
+        self.root.join(self.name)
+    }
+
+    pub fn disabled_path(&self) -> PathBuf {
+        self.root.join(self.disabled_folder)
+    }
+}
+
+/// A way of enabling and disabling mods for a game.
+///
+/// Implementations are registered in `STRATEGIES` and looked up by `id()`,
+/// which is the value stored in `Database.mod_strategy`.
+pub trait ModStrategy: Send + Sync {
+    fn id(&self) -> &'static str;
+    fn describe(&self) -> StrategyInfo;
+    fn enable(&self, ctx: &ModContext) -> Result<(), String>;
+    fn disable(&self, ctx: &ModContext) -> Result<(), String>;
+    fn is_enabled(&self, ctx: &ModContext) -> bool;
+
+    /// Directories whose subfolders are mods managed by this strategy.
+    /// Only the folder settings of `ctx` are used, not the mod name.
+    fn search_dirs(&self, ctx: &ModContext) -> Vec<PathBuf> {
+        vec![ctx.root.to_path_buf()]
+    }
+}
+
+pub const DEFAULT_STRATEGY: &str = "generic_rename";
+
+/// Strategy of the single-game `mod-manager.json`, which predates the
+/// strategy setting and was always Wuthering Waves.
+pub const LEGACY_STRATEGY: &str = "wuthering_waves";
+
+static STRATEGIES: &[&dyn ModStrategy] = &[
+    &GenericRename,
+    &WutheringWaves,
+    &LinkDeploy,
+];
+
+pub fn all() -> &'static [&'static dyn ModStrategy] {
+    STRATEGIES
+}
+
+pub fn get(id: &str) -> Option<&'static dyn ModStrategy> {
+    STRATEGIES.iter().copied().find(|s| s.id() == id)
+}
+
+pub fn resolve(id: &str) -> Result<&'static dyn ModStrategy, String> {
+    get(id).ok_or_else(|| {
+        let known: Vec<&str> = STRATEGIES.iter().map(|s| s.id()).collect();
+        format!("Unknown mod strategy: {} (available: {})", id, known.join(", "))
+    })
+}
+
+// ============ GENERIC (FOLDER MOVE) ============
+
+pub struct GenericRename;
+
+impl ModStrategy for GenericRename {
+    fn id(&self) -> &'static str {
+        "generic_rename"
+    }
+
+    fn describe(&self) -> StrategyInfo {
+        StrategyInfo {
+            id: self.id().to_string(),
+            name: String::from("Generic (Move to folder)"),
+            description: String::from("Moves mod folder to/from a disabled folder when toggling"),
+        }
+    }
+
+    fn enable(&self, ctx: &ModContext) -> Result<(), String> {
+        let mod_path = ctx.mod_path();
+        let source = ctx.disabled_path().join(folder_name(&mod_path)?);
+
+        if source.exists() {
+            transfer::move_dir(&source, &mod_path, &mut |_| Ok(()))
+                .map_err(|e| format!("Failed to enable mod: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    fn disable(&self, ctx: &ModContext) -> Result<(), String> {
+        let mod_path = ctx.mod_path();
+        let disabled_path = ctx.disabled_path();
+
+        if !disabled_path.exists() {
+            fs::create_dir_all(&disabled_path)
+                .map_err(|e| format!("Failed to create disabled folder: {}", e))?;
+        }
+
+        let destination = disabled_path.join(folder_name(&mod_path)?);
+        if mod_path.exists() {
+            transfer::move_dir(&mod_path, &destination, &mut |_| Ok(()))
+                .map_err(|e| format!("Failed to disable mod: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    fn is_enabled(&self, ctx: &ModContext) -> bool {
+        ctx.mod_path().exists()
+    }
+
+    fn search_dirs(&self, ctx: &ModContext) -> Vec<PathBuf> {
+        vec![ctx.root.to_path_buf(), ctx.disabled_path()]
+    }
+}
+
+fn folder_name(mod_path: &Path) -> Result<&std::ffi::OsStr, String> {
+    mod_path.file_name()
+        .ok_or_else(|| "Cannot get folder name".to_string())
+}
+
+// ============ WUTHERING WAVES (INI RENAME) ============
+
+pub struct WutheringWaves;
+
+impl ModStrategy for WutheringWaves {
+    fn id(&self) -> &'static str {
+        "wuthering_waves"
+    }
+
+    fn describe(&self) -> StrategyInfo {
+        StrategyInfo {
+            id: self.id().to_string(),
+            name: String::from("Wuthering Waves (.ini rename)"),
+            description: String::from("Adds/removes .bak extension to .ini files when toggling"),
+        }
+    }
+
+    fn enable(&self, ctx: &ModContext) -> Result<(), String> {
+        toggle_files_recursive(&ctx.mod_path(), true, "ini")
+    }
+
+    fn disable(&self, ctx: &ModContext) -> Result<(), String> {
+        toggle_files_recursive(&ctx.mod_path(), false, "ini")
+    }
+
+    // A mod is disabled once every ini has been renamed to .ini.bak; mods
+    // that ship optional .ini.bak files next to live ones still count as enabled.
+    fn is_enabled(&self, ctx: &ModContext) -> bool {
+        let mod_path = ctx.mod_path();
+        has_files_with_suffix(&mod_path, ".ini") || !has_files_with_suffix(&mod_path, ".ini.bak")
+    }
+}
+
+// ============ LINK DEPLOY (SYMLINK / HARDLINK) ============
+
+// Written into hardlink-deployed folders so disable can tell them apart
+// from a real mod folder that happens to share the name.
+const LINK_MARKER: &str = ".wwmm-deployed";
+
+pub struct LinkDeploy;
+
+impl LinkDeploy {
+    fn library_path(ctx: &ModContext) -> Result<PathBuf, String> {
+        if ctx.library_folder.is_empty() {
+            return Err("Mod library folder is not configured".to_string());
+        }
+        
+        Ok(Path::new(ctx.library_folder).join(ctx.name))
+    }
+}
+
+impl ModStrategy for LinkDeploy {
+    fn id(&self) -> &'static str {
+        "link_deploy"
+    }
+
+    fn describe(&self) -> StrategyInfo {
+        StrategyInfo {
+            id: self.id().to_string(),
+            name: String::from("Library (Symlink/Hardlink)"),
+            description: String::from("Keeps mods in a separate library folder and links them into the mod folder when enabled"),
+        }
+    }
+
+    fn enable(&self, ctx: &ModContext) -> Result<(), String> {
+        let source = Self::library_path(ctx)?;
+        let target = ctx.mod_path();
+
+        if !source.is_dir() {
+            return Err(format!("Mod is not in the library: {}", source.display()));
+        }
+
+        if is_symlink(&target) {
+            let current = fs::read_link(&target).map_err(|e| e.to_string())?;
+            if current == source {
+                return Ok(());
+            }
+            return Err(format!("{} is already linked to {}", target.display(), current.display()));
+        }
+
+        if target.exists() {
+            if !target.join(LINK_MARKER).exists() {
+                return Err(format!("A folder not managed by the library already exists: {}", target.display()));
+            }
+            // Previously hardlinked; pick up any files added to the library since
+            return hardlink_tree(&source, &target)
+                .map_err(|e| format!("Failed to link mod files: {}", e));
+        }
+
+        if symlink_dir(&source, &target).is_ok() {
+            return Ok(());
+        }
+
+        // Symlinks need elevated rights on Windows, so mirror the tree with hardlinks instead
+        let result = hardlink_tree(&source, &target)
+            .and_then(|_| fs::write(target.join(LINK_MARKER), ""));
+        if let Err(e) = result {
+            let _ = unlink_tree(&source, &target);
+            return Err(format!("Failed to link mod files: {}", e));
+        }
+
+        Ok(())
+    }
+
+    fn disable(&self, ctx: &ModContext) -> Result<(), String> {
+        let target = ctx.mod_path();
+
+        if is_symlink(&target) {
+            return remove_symlink_dir(&target)
+                .map_err(|e| format!("Failed to remove mod link: {}", e));
+        }
+
+        if !target.exists() {
+            return Ok(());
+        }
+
+        if !target.join(LINK_MARKER).exists() {
+            return Err(format!("Folder is not managed by the library: {}", target.display()));
+        }
+
+        let source = Self::library_path(ctx)?;
+        unlink_tree(&source, &target)
+            .map_err(|e| format!("Failed to remove mod links: {}", e))
+    }
+
+    fn is_enabled(&self, ctx: &ModContext) -> bool {
+        let target = ctx.mod_path();
+        is_symlink(&target) || target.join(LINK_MARKER).exists()
+    }
+
+    fn search_dirs(&self, ctx: &ModContext) -> Vec<PathBuf> {
+        if ctx.library_folder.is_empty() {
+            return vec![ctx.root.to_path_buf()];
+        }
+        vec![PathBuf::from(ctx.library_folder), ctx.root.to_path_buf()]
+    }
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path)
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
+#[cfg(unix)]
+fn symlink_dir(source: &Path, target: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(source, target)
+}
+
+#[cfg(windows)]
+fn symlink_dir(source: &Path, target: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_dir(source, target)
+}
+
+#[cfg(unix)]
+fn remove_symlink_dir(path: &Path) -> std::io::Result<()> {
+    fs::remove_file(path)
+}
+
+#[cfg(windows)]
+fn remove_symlink_dir(path: &Path) -> std::io::Result<()> {
+    fs::remove_dir(path)
+}
+
+fn hardlink_tree(src: &Path, dst: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dst)?;
+    
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+        
+        if entry.file_type()?.is_dir() {
+            hardlink_tree(&src_path, &dst_path)?;
+        } else if !dst_path.exists() {
+            fs::hard_link(&src_path, &dst_path)?;
+        }
+    }
+    
+    Ok(())
+}
+
+// Removes only the files that mirror something in the library, so anything
+// written into the deployed folder by the game or the user is left behind.
+fn unlink_tree(src: &Path, dst: &Path) -> std::io::Result<()> {
+    for entry in fs::read_dir(dst)? {
+        let entry = entry?;
+        let dst_path = entry.path();
+        let src_path = src.join(entry.file_name());
+        
+        if entry.file_type()?.is_dir() {
+            unlink_tree(&src_path, &dst_path)?;
+        } else if src_path.exists() || entry.file_name() == LINK_MARKER {
+            fs::remove_file(&dst_path)?;
+        }
+    }
+    
+    let _ = fs::remove_dir(dst);
+    Ok(())
+}
+
+/// Renames every `extension` file under `dir` to `.bak` or back. Either all
+/// of them are renamed or, if one rename fails, none are.
+pub fn toggle_files_recursive(dir: &Path, enable: bool, extension: &str) -> Result<(), String> {
+    let mut renames = Vec::new();
+    collect_renames(dir, enable, extension, &mut renames)?;
+
+    for (i, (from, to)) in renames.iter().enumerate() {
+        if let Err(e) = fs::rename(from, to) {
+            for (done_from, done_to) in renames[..i].iter().rev() {
+                let _ = fs::rename(done_to, done_from);
+            }
+            return Err(format!("Failed to rename {}: {}", from.display(), e));
+        }
+    }
+
+    Ok(())
+}
+
+fn collect_renames(dir: &Path, enable: bool, extension: &str, renames: &mut Vec<(PathBuf, PathBuf)>) -> Result<(), String> {
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_renames(&path, enable, extension, renames)?;
+        } else if let Some(ext) = path.extension() {
+            let ext_str = ext.to_string_lossy();
+
+            if enable {
+                if ext_str == "bak" {
+                    if let Some(stem) = path.file_stem() {
+                        let stem_str = stem.to_string_lossy();
+                        if stem_str.ends_with(&format!(".{}", extension)) {
+                            let new_path = path.with_extension("");
+                            renames.push((path, new_path));
+                        }
+                    }
+                }
+            } else if ext_str == extension {
+                let new_path = PathBuf::from(format!("{}.bak", path.display()));
+                renames.push((path, new_path));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn has_files_with_suffix(dir: &Path, suffix: &str) -> bool {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if has_files_with_suffix(&path, suffix) {
+                return true;
+            }
+        } else if path.to_string_lossy().to_lowercase().ends_with(suffix) {
+            return true;
+        }
+    }
+
+    false
+}
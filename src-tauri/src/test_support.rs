@@ -0,0 +1,33 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+
+thread_local! {
+    static DATA_DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Data folder of the test running on this thread, if it set one.
+pub fn data_dir() -> Option<PathBuf> {
+    DATA_DIR.with(|dir| dir.borrow().clone())
+}
+
+/// A scratch data folder used in place of the user's for as long as it is
+/// held. Each test runs on its own thread, so tests don't see each other's.
+pub struct TestDataDir(pub PathBuf);
+
+impl TestDataDir {
+    pub fn new(label: &str) -> TestDataDir {
+        let path = std::env::temp_dir().join(format!("wwmm-data-{}-{}", label, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        DATA_DIR.with(|dir| *dir.borrow_mut() = Some(path.clone()));
+        TestDataDir(path)
+    }
+}
+
+impl Drop for TestDataDir {
+    fn drop(&mut self) {
+        DATA_DIR.with(|dir| *dir.borrow_mut() = None);
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
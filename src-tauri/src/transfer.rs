@@ -0,0 +1,424 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::jobs::JobProgress;
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+// Large texture packs copy thousands of chunks a second; the UI only needs a
+// few updates per second
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Receives copy progress. Returning an error, such as a cancellation, stops
+/// the copy.
+pub type Report<'a> = &'a mut dyn FnMut(&JobProgress) -> Result<(), String>;
+
+struct Tracker<'a> {
+    progress: JobProgress,
+    started: Instant,
+    last_report: Option<Instant>,
+    report: Report<'a>,
+}
+
+impl Tracker<'_> {
+    fn tick(&mut self, force: bool) -> Result<(), String> {
+        if !force && self.last_report.is_some_and(|last| last.elapsed() < REPORT_INTERVAL) {
+            return Ok(());
+        }
+
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.progress.bytes_per_second = (self.progress.bytes_done as f64 / elapsed) as u64;
+        }
+        self.last_report = Some(Instant::now());
+        (self.report)(&self.progress)
+    }
+}
+
+/// Number and total size of the files under `dir`. Links count as files
+/// without looking at what they point to.
+pub fn dir_totals(dir: &Path) -> io::Result<(usize, u64)> {
+    let mut totals = (0, 0);
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            let (files, bytes) = dir_totals(&entry.path())?;
+            totals.0 += files;
+            totals.1 += bytes;
+        } else {
+            totals.0 += 1;
+            if !file_type.is_symlink() {
+                totals.1 += entry.metadata()?.len();
+            }
+        }
+    }
+
+    Ok(totals)
+}
+
+fn copy_file(src: &Path, dst: &Path, tracker: &mut Tracker) -> Result<(), String> {
+    let mut input = fs::File::open(src)
+        .map_err(|e| format!("Failed to open {}: {}", src.display(), e))?;
+    let mut output = fs::File::create(dst)
+        .map_err(|e| format!("Failed to create {}: {}", dst.display(), e))?;
+
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    loop {
+        let read = input.read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+        if read == 0 {
+            break;
+        }
+
+        output.write_all(&buffer[..read])
+            .map_err(|e| format!("Failed to write {}: {}", dst.display(), e))?;
+        tracker.progress.bytes_done += read as u64;
+        tracker.tick(false)?;
+    }
+
+    if let Ok(metadata) = input.metadata() {
+        let _ = fs::set_permissions(dst, metadata.permissions());
+    }
+    Ok(())
+}
+
+// Recreates the link `src` at `dst` pointing at the same target.
+fn copy_link(src: &Path, dst: &Path) -> Result<(), String> {
+    let target = fs::read_link(src)
+        .map_err(|e| format!("Failed to read link {}: {}", src.display(), e))?;
+    symlink(src, &target, dst)
+        .map_err(|e| format!("Failed to create link {}: {}", dst.display(), e))
+}
+
+#[cfg(unix)]
+fn symlink(_src: &Path, target: &Path, dst: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, dst)
+}
+
+// Windows has separate links for files and folders
+#[cfg(windows)]
+fn symlink(src: &Path, target: &Path, dst: &Path) -> io::Result<()> {
+    if fs::metadata(src).is_ok_and(|m| m.is_dir()) {
+        std::os::windows::fs::symlink_dir(target, dst)
+    } else {
+        std::os::windows::fs::symlink_file(target, dst)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(_src: &Path, _target: &Path, _dst: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "links are not supported"))
+}
+
+fn copy_tree(base: &Path, src: &Path, dst: &Path, tracker: &mut Tracker) -> Result<(), String> {
+    if !dst.exists() {
+        fs::create_dir(dst)
+            .map_err(|e| format!("Failed to create {}: {}", dst.display(), e))?;
+    }
+
+    let entries = fs::read_dir(src)
+        .map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+
+        // `file_type` doesn't follow links, so a link to a folder is copied
+        // as a link rather than walked into, which could loop or copy a
+        // whole library
+        let file_type = entry.file_type()
+            .map_err(|e| format!("Failed to read {}: {}", src_path.display(), e))?;
+        if file_type.is_dir() {
+            copy_tree(base, &src_path, &dst_path, tracker)?;
+            continue;
+        }
+
+        tracker.progress.current_path = src_path.strip_prefix(base).unwrap_or(&src_path).to_string_lossy().to_string();
+        if file_type.is_symlink() {
+            copy_link(&src_path, &dst_path)?;
+        } else {
+            copy_file(&src_path, &dst_path, tracker)?;
+        }
+        tracker.progress.files_done += 1;
+        tracker.tick(false)?;
+    }
+
+    Ok(())
+}
+
+/// Copies the folder `src` to `dst`, reporting bytes and files copied and the
+/// throughput at most every `REPORT_INTERVAL`, and once more at the end.
+pub fn copy_dir(src: &Path, dst: &Path, report: Report) -> Result<(), String> {
+    let (files_total, bytes_total) = dir_totals(src)
+        .map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+
+    let mut tracker = Tracker {
+        progress: JobProgress { files_total, bytes_total, ..Default::default() },
+        started: Instant::now(),
+        last_report: None,
+        report,
+    };
+
+    copy_tree(src, src, dst, &mut tracker)?;
+    tracker.tick(true)
+}
+
+// `fs::rename` refuses to move between filesystems: EXDEV on Unix,
+// ERROR_NOT_SAME_DEVICE on Windows.
+fn is_cross_device(e: &io::Error) -> bool {
+    #[cfg(unix)]
+    const CROSS_DEVICE: i32 = 18;
+    #[cfg(windows)]
+    const CROSS_DEVICE: i32 = 17;
+    #[cfg(not(any(unix, windows)))]
+    const CROSS_DEVICE: i32 = -1;
+
+    e.kind() == io::ErrorKind::CrossesDevices || e.raw_os_error() == Some(CROSS_DEVICE)
+}
+
+// Checks that every file under `src` exists under `dst` with the same
+// contents, and every link with the same target.
+fn verify_copy(src: &Path, dst: &Path) -> Result<(), String> {
+    let entries = fs::read_dir(src)
+        .map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+        let file_type = entry.file_type()
+            .map_err(|e| format!("Failed to read {}: {}", src_path.display(), e))?;
+
+        if file_type.is_dir() {
+            verify_copy(&src_path, &dst_path)?;
+            continue;
+        }
+
+        let matches = if file_type.is_symlink() {
+            matches!((fs::read_link(&src_path), fs::read_link(&dst_path)), (Ok(a), Ok(b)) if a == b)
+        } else {
+            same_contents(&src_path, &dst_path).unwrap_or(false)
+        };
+
+        if !matches {
+            return Err(format!("Copy of {} does not match the original", src_path.display()));
+        }
+    }
+
+    Ok(())
+}
+
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let mut a = fs::File::open(a)?;
+    let mut b = fs::File::open(b)?;
+    if a.metadata()?.len() != b.metadata()?.len() {
+        return Ok(false);
+    }
+
+    let mut a_buffer = vec![0u8; CHUNK_SIZE];
+    let mut b_buffer = vec![0u8; CHUNK_SIZE];
+    loop {
+        let read = a.read(&mut a_buffer)?;
+        if read == 0 {
+            return Ok(true);
+        }
+
+        b.read_exact(&mut b_buffer[..read])?;
+        if a_buffer[..read] != b_buffer[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+// Copies `src` to `dst` and checks the result, removing whatever was copied
+// if either step fails so `src` remains the only copy.
+fn verified_copy(src: &Path, dst: &Path, report: Report) -> Result<(), String> {
+    let result = copy_dir(src, dst, report).and_then(|_| verify_copy(src, dst));
+
+    if result.is_err() {
+        let _ = fs::remove_dir_all(dst);
+    }
+    result
+}
+
+/// Moves the folder `src` to `dst`. A rename can't cross drives, so there the
+/// folder is copied with progress, verified, and only then is the original
+/// removed. If any step fails the folder is left where it was.
+pub fn move_dir(src: &Path, dst: &Path, report: Report) -> Result<(), String> {
+    match fs::rename(src, dst) {
+        Ok(()) => Ok(()),
+        Err(e) if is_cross_device(&e) => move_by_copy(src, dst, report),
+        Err(e) => Err(format!("Failed to move {}: {}", src.display(), e)),
+    }
+}
+
+fn move_by_copy(src: &Path, dst: &Path, report: Report) -> Result<(), String> {
+    // Copying into an existing folder would merge the two, and a failed copy
+    // would then remove a folder that was there before
+    if dst.exists() {
+        return Err(format!("Failed to move {}: {} already exists", src.display(), dst.display()));
+    }
+
+    verified_copy(src, dst, report)?;
+
+    if let Err(e) = fs::remove_dir_all(src) {
+        // Part of the original may be gone already, so fill it back in from
+        // the copy before dropping the copy
+        let restored = copy_dir(dst, src, &mut |_| Ok(())).and_then(|_| verify_copy(dst, src));
+        return match restored {
+            Ok(()) => {
+                let _ = fs::remove_dir_all(dst);
+                Err(format!("Failed to remove {} after copying it, so the move was undone: {}", src.display(), e))
+            }
+            Err(restore_error) => Err(format!(
+                "Failed to remove {} after copying it to {} ({}), and failed to restore it: {}",
+                src.display(), dst.display(), e, restore_error
+            )),
+        };
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("wwmm-transfer-{}-{}", label, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn sample_mod(dir: &Path) -> PathBuf {
+        let src = dir.join("Mod");
+        write(&src.join("mod.ini"), "[TextureOverrideBody]\n");
+        write(&src.join("textures/body.dds"), "texture data");
+        src
+    }
+
+    #[test]
+    fn copies_a_folder_with_progress() {
+        let dir = TempDir::new("copy");
+        let src = sample_mod(&dir.0);
+        fs::create_dir(src.join("empty")).unwrap();
+        let dst = dir.0.join("Copy");
+
+        assert_eq!(dir_totals(&src).unwrap(), (2, 34));
+
+        let mut last = JobProgress::default();
+        copy_dir(&src, &dst, &mut |progress| { last = progress.clone(); Ok(()) }).unwrap();
+
+        assert_eq!((last.files_done, last.files_total), (2, 2));
+        assert_eq!((last.bytes_done, last.bytes_total), (34, 34));
+        assert_eq!(fs::read_to_string(dst.join("textures/body.dds")).unwrap(), "texture data");
+        assert!(dst.join("empty").is_dir());
+        assert!(src.join("mod.ini").exists());
+        verify_copy(&src, &dst).unwrap();
+    }
+
+    #[test]
+    fn verification_compares_contents() {
+        let dir = TempDir::new("verify");
+        let src = sample_mod(&dir.0);
+        let dst = dir.0.join("Copy");
+        copy_dir(&src, &dst, &mut |_| Ok(())).unwrap();
+
+        // Same size, different bytes
+        write(&dst.join("textures/body.dds"), "texture_data");
+        assert!(verify_copy(&src, &dst).is_err());
+
+        write(&dst.join("textures/body.dds"), "texture data");
+        fs::remove_file(dst.join("mod.ini")).unwrap();
+        assert!(verify_copy(&src, &dst).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copies_links_without_following_them() {
+        let dir = TempDir::new("links");
+        let src = sample_mod(&dir.0);
+        let library = dir.0.join("Library");
+        write(&library.join("huge.dds"), "library data");
+        std::os::unix::fs::symlink(&library, src.join("library")).unwrap();
+        // Following this one would recurse forever
+        std::os::unix::fs::symlink("..", src.join("textures/parent")).unwrap();
+        let dst = dir.0.join("Copy");
+
+        assert_eq!(dir_totals(&src).unwrap(), (4, 34));
+        copy_dir(&src, &dst, &mut |_| Ok(())).unwrap();
+
+        assert_eq!(fs::read_link(dst.join("library")).unwrap(), library);
+        assert_eq!(fs::read_link(dst.join("textures/parent")).unwrap(), Path::new(".."));
+        verify_copy(&src, &dst).unwrap();
+
+        fs::remove_file(dst.join("library")).unwrap();
+        std::os::unix::fs::symlink(dir.0.join("Elsewhere"), dst.join("library")).unwrap();
+        assert!(verify_copy(&src, &dst).is_err());
+    }
+
+    #[test]
+    fn moves_by_copy() {
+        let dir = TempDir::new("move");
+        let src = sample_mod(&dir.0);
+        let dst = dir.0.join("Moved");
+
+        let mut reports = 0;
+        move_by_copy(&src, &dst, &mut |_| { reports += 1; Ok(()) }).unwrap();
+
+        assert!(!src.exists());
+        assert_eq!(fs::read_to_string(dst.join("textures/body.dds")).unwrap(), "texture data");
+        assert!(reports > 0);
+    }
+
+    #[test]
+    fn failed_copy_leaves_the_original() {
+        let dir = TempDir::new("cancel");
+        let src = sample_mod(&dir.0);
+        let dst = dir.0.join("Moved");
+
+        let result = move_by_copy(&src, &dst, &mut |progress| {
+            if progress.files_done >= 1 { Err("Cancelled".to_string()) } else { Ok(()) }
+        });
+
+        assert_eq!(result, Err("Cancelled".to_string()));
+        assert!(!dst.exists());
+        assert_eq!(fs::read_to_string(src.join("mod.ini")).unwrap(), "[TextureOverrideBody]\n");
+        assert_eq!(fs::read_to_string(src.join("textures/body.dds")).unwrap(), "texture data");
+    }
+
+    #[test]
+    fn refuses_an_existing_destination() {
+        let dir = TempDir::new("exists");
+        let src = sample_mod(&dir.0);
+        let dst = dir.0.join("Moved");
+        write(&dst.join("keep.txt"), "already here");
+
+        assert!(move_by_copy(&src, &dst, &mut |_| Err("Cancelled".to_string())).is_err());
+
+        assert_eq!(fs::read_to_string(dst.join("keep.txt")).unwrap(), "already here");
+        assert!(!dst.join("mod.ini").exists());
+        assert!(src.join("mod.ini").exists());
+    }
+}
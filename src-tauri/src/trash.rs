@@ -0,0 +1,280 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::watcher::ModWatcher;
+use crate::{get_data_dir, load_game_db, paths, storage, strategy, transfer, write_game_db, Mod};
+
+// Each deleted mod gets a folder holding its files and this metadata
+const ENTRY_FILE: &str = "entry.json";
+const FILES_DIR: &str = "files";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrashEntry {
+    pub id: String,
+    pub name: String,
+    pub original_path: String,
+    pub game_id: Option<i64>,
+    // The database record, so a restore brings back notes, tags and presets
+    pub record: Option<Mod>,
+    pub deleted_at: u64,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn trash_dir() -> PathBuf {
+    get_data_dir().join("trash")
+}
+
+fn read_entry(dir: &Path) -> Result<TrashEntry, String> {
+    storage::read_json(&dir.join(ENTRY_FILE))
+}
+
+/// Moves the mod folder at `mod_path` into the trash. `game_id` and the mod's
+/// database record are kept so it can be restored into the game later.
+pub fn move_to_trash(mod_path: &Path, name: &str, game_id: Option<i64>, record: Option<Mod>) -> Result<TrashEntry, String> {
+    let deleted_at = now_millis();
+
+    // Two mods deleted in the same millisecond still need their own folders
+    let mut id = deleted_at.to_string();
+    let mut suffix = 1;
+    while trash_dir().join(&id).exists() {
+        id = format!("{}-{}", deleted_at, suffix);
+        suffix += 1;
+    }
+
+    let entry_dir = trash_dir().join(&id);
+    fs::create_dir_all(&entry_dir)
+        .map_err(|e| format!("Failed to create trash folder: {}", e))?;
+
+    let entry = TrashEntry {
+        id,
+        name: name.to_string(),
+        original_path: mod_path.to_string_lossy().to_string(),
+        game_id,
+        record,
+        deleted_at,
+    };
+
+    // Metadata first, so a trashed folder is never left without it
+    let result = storage::write_json(&entry_dir.join(ENTRY_FILE), &entry)
+        .and_then(|_| transfer::move_dir(mod_path, &entry_dir.join(FILES_DIR), &mut |_| Ok(())));
+    if let Err(e) = result {
+        let _ = fs::remove_dir_all(&entry_dir);
+        return Err(e);
+    }
+
+    Ok(entry)
+}
+
+/// Trashed mods, newest first, optionally only those of one game.
+#[tauri::command]
+pub fn list_trash(game_id: Option<i64>) -> Result<Vec<TrashEntry>, String> {
+    let entries = match fs::read_dir(trash_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(vec![]),
+    };
+
+    let mut trashed: Vec<TrashEntry> = entries
+        .flatten()
+        .filter_map(|entry| read_entry(&entry.path()).ok())
+        .filter(|entry| game_id.is_none() || entry.game_id == game_id)
+        .collect();
+
+    trashed.sort_by_key(|entry| std::cmp::Reverse(entry.deleted_at));
+    Ok(trashed)
+}
+
+/// Moves a trashed mod back to where it was deleted from and, if it belonged
+/// to a game, puts its record back into that game's database.
+#[tauri::command]
+pub fn restore_from_trash(id: String, watcher: tauri::State<'_, ModWatcher>) -> Result<TrashEntry, String> {
+    let _pause = watcher.pause();
+    restore(&id)
+}
+
+pub fn restore(id: &str) -> Result<TrashEntry, String> {
+    paths::check_name(id)?;
+    let entry_dir = trash_dir().join(id);
+    let entry = read_entry(&entry_dir)
+        .map_err(|e| format!("Trash entry not found: {} ({})", id, e))?;
+
+    let original = PathBuf::from(&entry.original_path);
+    let parent = original.parent()
+        .and_then(|dir| dir.to_str())
+        .ok_or_else(|| format!("Invalid original path: {}", entry.original_path))?;
+    let db = match entry.game_id {
+        Some(game_id) => Some(load_game_db(game_id)?),
+        None => None,
+    };
+
+    // The game's folders may have changed since the mod was deleted
+    let target = match &db {
+        Some(db) => paths::game_entry_path(db, parent, &entry.name)?,
+        None => paths::entry_path(parent, &entry.name)?,
+    };
+
+    if target.exists() {
+        return Err(format!("A mod already exists at {}", target.display()));
+    }
+
+    transfer::move_dir(&entry_dir.join(FILES_DIR), &target, &mut |_| Ok(()))?;
+
+    if let (Some(game_id), Some(mut db), Some(mut record)) = (entry.game_id, db, entry.record.clone()) {
+        if !db.mods.iter().any(|m| m.id == record.id || m.name == record.name) {
+            // Link strategies drop the link on delete, so the mod comes back disabled
+            if let Ok(strategy) = strategy::resolve(&db.mod_strategy) {
+                record.enabled = strategy.is_enabled(&db.mod_context(&record.name));
+            }
+            db.mods.push(record);
+            write_game_db(game_id, db)?;
+        }
+    }
+
+    let _ = fs::remove_dir_all(&entry_dir);
+    Ok(entry)
+}
+
+/// Permanently deletes trashed mods deleted before `older_than` (milliseconds
+/// since the epoch), or all of them. Returns how many were purged.
+#[tauri::command]
+pub fn purge_trash(older_than: Option<u64>) -> Result<usize, String> {
+    let mut purged = 0;
+
+    for entry in list_trash(None)? {
+        if older_than.is_some_and(|cutoff| entry.deleted_at >= cutoff) {
+            continue;
+        }
+
+        fs::remove_dir_all(trash_dir().join(&entry.id))
+            .map_err(|e| format!("Failed to purge {}: {}", entry.name, e))?;
+        purged += 1;
+    }
+
+    Ok(purged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestDataDir;
+    use crate::{create_game, Database};
+    use serde_json::json;
+
+    // A game with the enabled mod A whose folder is at `Mods/A`, returning
+    // the game id
+    fn game(data: &TestDataDir) -> i64 {
+        let root = data.0.join("Mods");
+        fs::create_dir_all(root.join("_Disabled")).unwrap();
+        fs::create_dir_all(root.join("A/textures")).unwrap();
+        fs::write(root.join("A/mod.ini"), "[Constants]\n").unwrap();
+        fs::write(root.join("A/textures/body.dds"), "texture data").unwrap();
+
+        let db: Database = serde_json::from_value(json!({
+            "root_folder": root,
+            "disabled_folder": "_Disabled",
+            "mod_strategy": "generic_rename",
+            "categories": [],
+            "mods": [{
+                "id": 1,
+                "name": "A",
+                "category_id": 0,
+                "enabled": true,
+                "notes": "favourite",
+                "preview": null,
+                "tags": ["Outfit"],
+            }],
+            "tags": ["Outfit"],
+            "tag_metadata": [],
+            "profiles": [],
+        })).unwrap();
+        create_game("Test".to_string(), String::new(), None, &db).unwrap().id
+    }
+
+    // Trashes mod A the way deleting it does, dropping its record
+    fn delete(data: &TestDataDir, game_id: i64) -> TrashEntry {
+        let mut db = load_game_db(game_id).unwrap();
+        let record = db.mods.pop();
+        let entry = move_to_trash(&data.0.join("Mods/A"), "A", Some(game_id), record).unwrap();
+        write_game_db(game_id, db).unwrap();
+        entry
+    }
+
+    #[test]
+    fn restores_a_mod_and_its_record() {
+        let data = TestDataDir::new("trash-restore");
+        let game_id = game(&data);
+
+        let entry = delete(&data, game_id);
+        assert!(!data.0.join("Mods/A").exists());
+        assert_eq!(list_trash(Some(game_id)).unwrap().len(), 1);
+        assert!(list_trash(Some(game_id + 1)).unwrap().is_empty());
+
+        restore(&entry.id).unwrap();
+
+        assert_eq!(fs::read_to_string(data.0.join("Mods/A/textures/body.dds")).unwrap(), "texture data");
+        let db = load_game_db(game_id).unwrap();
+        assert_eq!(db.mods.len(), 1);
+        assert_eq!((db.mods[0].notes.as_str(), db.mods[0].enabled), ("favourite", true));
+        assert!(list_trash(None).unwrap().is_empty());
+        assert!(!trash_dir().join(&entry.id).exists());
+    }
+
+    #[test]
+    fn restores_a_mod_without_a_game() {
+        let data = TestDataDir::new("trash-no-game");
+        game(&data);
+        fs::create_dir_all(data.0.join("Mods/_Disabled/B")).unwrap();
+        fs::write(data.0.join("Mods/_Disabled/B/mod.ini"), "[Constants]\n").unwrap();
+
+        let entry = move_to_trash(&data.0.join("Mods/_Disabled/B"), "B", None, None).unwrap();
+        restore(&entry.id).unwrap();
+
+        assert!(data.0.join("Mods/_Disabled/B/mod.ini").exists());
+        assert_eq!(load_game_db(1).unwrap().mods.len(), 1);
+    }
+
+    #[test]
+    fn refuses_to_restore_onto_an_existing_mod() {
+        let data = TestDataDir::new("trash-taken");
+        let game_id = game(&data);
+        let entry = delete(&data, game_id);
+        fs::create_dir_all(data.0.join("Mods/A")).unwrap();
+        fs::write(data.0.join("Mods/A/mod.ini"), "installed since").unwrap();
+
+        let error = restore(&entry.id).unwrap_err();
+
+        assert!(error.starts_with("A mod already exists at"), "{}", error);
+        assert_eq!(fs::read_to_string(data.0.join("Mods/A/mod.ini")).unwrap(), "installed since");
+        assert!(load_game_db(game_id).unwrap().mods.is_empty());
+        let trashed = list_trash(None).unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert!(trash_dir().join(&entry.id).join(FILES_DIR).join("textures/body.dds").exists());
+    }
+
+    #[test]
+    fn purges_entries_older_than_the_cutoff() {
+        let data = TestDataDir::new("trash-purge");
+        let game_id = game(&data);
+        let old = delete(&data, game_id);
+        fs::create_dir_all(data.0.join("Mods/B")).unwrap();
+        let new = move_to_trash(&data.0.join("Mods/B"), "B", Some(game_id), None).unwrap();
+
+        // Backdate the first entry
+        let entry_path = trash_dir().join(&old.id).join(ENTRY_FILE);
+        storage::write_json(&entry_path, &TrashEntry { deleted_at: 1000, ..old }).unwrap();
+
+        assert_eq!(purge_trash(Some(2000)).unwrap(), 1);
+        let left: Vec<String> = list_trash(None).unwrap().into_iter().map(|entry| entry.id).collect();
+        assert_eq!(left, [new.id]);
+
+        assert_eq!(purge_trash(None).unwrap(), 1);
+        assert!(list_trash(None).unwrap().is_empty());
+    }
+}
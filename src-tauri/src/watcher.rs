@@ -0,0 +1,366 @@
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{load_game_db, load_games, strategy, Database};
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ModFolderEvent {
+    pub game_id: i64,
+    pub mod_name: String,
+    pub change: &'static str,
+}
+
+// Events for a change can arrive a little after the call that made it returns
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+// The manager's own operations on mod folders, which the watcher doesn't
+// report: the manager updates the database for them itself. Changes seen
+// meanwhile are held back and checked against the database once it is done,
+// so a change made outside the manager at the same time isn't lost.
+#[derive(Default)]
+struct Busy {
+    operations: usize,
+    finished_at: Option<Instant>,
+    held: Vec<ModFolderEvent>,
+    app: Option<AppHandle>,
+}
+
+impl Busy {
+    fn is_busy(&self) -> bool {
+        self.operations > 0 || self.finished_at.is_some_and(|at| at.elapsed() < SETTLE_TIME)
+    }
+}
+
+/// Held while the manager changes mod folders itself. Events are held back
+/// until shortly after the last pause ends, and only those the database
+/// doesn't account for by then are reported.
+pub struct Pause {
+    busy: Arc<Mutex<Busy>>,
+}
+
+impl Drop for Pause {
+    fn drop(&mut self) {
+        let mut busy = self.busy.lock().unwrap();
+        busy.operations -= 1;
+        busy.finished_at = Some(Instant::now());
+
+        if busy.operations == 0 {
+            let shared = self.busy.clone();
+            thread::spawn(move || {
+                thread::sleep(SETTLE_TIME);
+                release_held(&shared);
+            });
+        }
+    }
+}
+
+// Reports the held changes that the database still disagrees with.
+fn release_held(busy: &Mutex<Busy>) {
+    let (app, held) = {
+        let mut busy = busy.lock().unwrap();
+        // Another operation has started or ended since, and releases them itself
+        if busy.is_busy() {
+            return;
+        }
+        (busy.app.clone(), std::mem::take(&mut busy.held))
+    };
+    let Some(app) = app else {
+        return;
+    };
+
+    for change in held {
+        if load_game_db(change.game_id).map_or(true, |db| is_unaccounted(&db, &change)) {
+            let _ = app.emit("mod-folder-changed", change);
+        }
+    }
+}
+
+// Whether `change` disagrees with `db`, meaning something other than the
+// manager made it.
+fn is_unaccounted(db: &Database, change: &ModFolderEvent) -> bool {
+    let Ok(strategy) = strategy::resolve(&db.mod_strategy) else {
+        return true;
+    };
+    let record = db.mods.iter().find(|m| m.name == change.mod_name);
+    let ctx = db.mod_context(&change.mod_name);
+
+    match (change.change, record) {
+        ("added", record) => record.is_none(),
+        ("removed", Some(_)) => !strategy.search_dirs(&ctx).iter().any(|dir| dir.join(&change.mod_name).exists()),
+        ("ini_renamed", Some(m)) => strategy.is_enabled(&ctx) != m.enabled,
+        _ => false,
+    }
+}
+
+/// Keeps one recursive watcher per game on its mod root folder.
+#[derive(Default)]
+pub struct ModWatcher {
+    watchers: Mutex<HashMap<i64, RecommendedWatcher>>,
+    busy: Arc<Mutex<Busy>>,
+}
+
+impl ModWatcher {
+    pub fn watch(&self, app: &AppHandle, game_id: i64, root: &str, disabled_folder: &str) -> Result<(), String> {
+        let root = PathBuf::from(root);
+        let disabled = root.join(disabled_folder);
+
+        if !root.is_dir() {
+            self.unwatch(game_id);
+            return Err(format!("Mod folder does not exist: {}", root.display()));
+        }
+
+        self.busy.lock().unwrap().app = Some(app.clone());
+
+        let app = app.clone();
+        let event_root = root.clone();
+        let busy = self.busy.clone();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+            let Ok(event) = res else {
+                return;
+            };
+            let changes = classify_event(game_id, &event, &event_root, &disabled);
+
+            let mut busy = busy.lock().unwrap();
+            if busy.is_busy() {
+                for change in changes {
+                    if !busy.held.contains(&change) {
+                        busy.held.push(change);
+                    }
+                }
+                return;
+            }
+            drop(busy);
+
+            for change in changes {
+                let _ = app.emit("mod-folder-changed", change);
+            }
+        }).map_err(|e| format!("Failed to create folder watcher: {}", e))?;
+
+        watcher.watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+
+        // Replacing the old watcher drops it, which stops watching the previous root
+        self.watchers.lock().unwrap().insert(game_id, watcher);
+        Ok(())
+    }
+
+    pub fn unwatch(&self, game_id: i64) {
+        self.watchers.lock().unwrap().remove(&game_id);
+    }
+
+    pub fn pause(&self) -> Pause {
+        self.busy.lock().unwrap().operations += 1;
+        Pause { busy: self.busy.clone() }
+    }
+}
+
+/// Starts watching every game at launch. Games that can't be watched are
+/// skipped: the window isn't listening yet, and selecting the game calls
+/// `watch_game` again, which reports the error.
+pub fn watch_all_games(app: &AppHandle) {
+    let state = app.state::<ModWatcher>();
+
+    for game in load_games().unwrap_or_default() {
+        if let Ok(db) = load_game_db(game.id) {
+            let _ = state.watch(app, game.id, &db.root_folder, &db.disabled_folder);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn watch_game(
+    game_id: i64,
+    root_folder: String,
+    disabled_folder: String,
+    app: AppHandle,
+    state: tauri::State<'_, ModWatcher>
+) -> Result<(), String> {
+    state.watch(&app, game_id, &root_folder, &disabled_folder)
+}
+
+#[tauri::command]
+pub fn unwatch_game(game_id: i64, state: tauri::State<'_, ModWatcher>) {
+    state.unwatch(game_id);
+}
+
+fn classify_event(game_id: i64, event: &Event, root: &Path, disabled: &Path) -> Vec<ModFolderEvent> {
+    let mut changes: Vec<ModFolderEvent> = Vec::new();
+
+    let mut push = |path: &Path, appeared: bool| {
+        let (mod_name, top_level) = match mod_name_for(path, root, disabled) {
+            Some(found) => found,
+            None => return,
+        };
+
+        let change = if top_level {
+            if appeared { "added" } else { "removed" }
+        } else if is_ini_file(path) && matches!(event.kind, EventKind::Modify(ModifyKind::Name(_))) {
+            "ini_renamed"
+        } else {
+            return;
+        };
+
+        let change = ModFolderEvent { game_id, mod_name, change };
+        if !changes.contains(&change) {
+            changes.push(change);
+        }
+    };
+
+    match event.kind {
+        EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+            for path in &event.paths {
+                push(path, true);
+            }
+        }
+        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            for path in &event.paths {
+                push(path, false);
+            }
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+            push(&event.paths[0], false);
+            push(&event.paths[1], true);
+        }
+        EventKind::Modify(ModifyKind::Name(_)) => {
+            for path in &event.paths {
+                push(path, path.exists());
+            }
+        }
+        _ => {}
+    }
+
+    changes
+}
+
+// Maps a changed path to the mod folder it belongs to, and whether the path
+// is the mod folder itself rather than something inside it.
+fn mod_name_for(path: &Path, root: &Path, disabled: &Path) -> Option<(String, bool)> {
+    let relative = path.strip_prefix(disabled)
+        .or_else(|_| path.strip_prefix(root))
+        .ok()?;
+
+    let mut components = relative.components();
+    let mod_name = components.next()?.as_os_str().to_string_lossy().to_string();
+    let top_level = components.next().is_none();
+
+    Some((mod_name, top_level))
+}
+
+fn is_ini_file(path: &Path) -> bool {
+    let name = path.to_string_lossy().to_lowercase();
+    name.ends_with(".ini") || name.ends_with(".ini.bak")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, RemoveKind};
+    use std::fs;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("wwmm-watcher-{}-{}", label, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn classify(kind: EventKind, paths: &[&str]) -> Vec<(String, &'static str)> {
+        let mut event = Event::new(kind);
+        for path in paths {
+            event = event.add_path(Path::new("/mods").join(path));
+        }
+
+        classify_event(1, &event, Path::new("/mods"), Path::new("/mods/_Disabled"))
+            .into_iter()
+            .map(|change| (change.mod_name, change.change))
+            .collect()
+    }
+
+    fn change(name: &str, change: &'static str) -> ModFolderEvent {
+        ModFolderEvent { game_id: 1, mod_name: name.to_string(), change }
+    }
+
+    #[test]
+    fn finds_the_mod_of_a_path() {
+        let root = Path::new("/mods");
+        let disabled = Path::new("/mods/_Disabled");
+
+        assert_eq!(mod_name_for(Path::new("/mods/A"), root, disabled), Some(("A".to_string(), true)));
+        assert_eq!(mod_name_for(Path::new("/mods/A/tex/a.dds"), root, disabled), Some(("A".to_string(), false)));
+        assert_eq!(mod_name_for(Path::new("/mods/_Disabled/B"), root, disabled), Some(("B".to_string(), true)));
+        assert_eq!(mod_name_for(Path::new("/mods/_Disabled"), root, disabled), None);
+        assert_eq!(mod_name_for(Path::new("/mods"), root, disabled), None);
+        assert_eq!(mod_name_for(Path::new("/other/A"), root, disabled), None);
+    }
+
+    #[test]
+    fn classifies_folder_events() {
+        assert_eq!(classify(EventKind::Create(CreateKind::Folder), &["A"]), [("A".to_string(), "added")]);
+        assert_eq!(classify(EventKind::Remove(RemoveKind::Folder), &["_Disabled/A"]), [("A".to_string(), "removed")]);
+        assert_eq!(
+            classify(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["A", "_Disabled/A"]),
+            [("A".to_string(), "removed"), ("A".to_string(), "added")]
+        );
+        assert_eq!(
+            classify(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["A/mod.ini", "A/mod.ini.bak"]),
+            [("A".to_string(), "ini_renamed")]
+        );
+    }
+
+    #[test]
+    fn ignores_changes_inside_mods() {
+        assert!(classify(EventKind::Create(CreateKind::File), &["A/tex/a.dds"]).is_empty());
+        assert!(classify(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &["A/mod.ini"]).is_empty());
+        assert!(classify(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["A/a.dds", "A/b.dds"]).is_empty());
+        assert!(classify(EventKind::Create(CreateKind::Folder), &["_Disabled"]).is_empty());
+    }
+
+    #[test]
+    fn checks_held_changes_against_the_database() {
+        let dir = TempDir::new("held");
+        let root = dir.0.join("Mods");
+        fs::create_dir_all(root.join("A")).unwrap();
+        fs::create_dir_all(root.join("_Disabled/B")).unwrap();
+        fs::create_dir_all(root.join("New")).unwrap();
+
+        let record = |id: i64, name: &str, enabled: bool| serde_json::json!({
+            "id": id, "name": name, "category_id": 0, "enabled": enabled,
+            "notes": "", "preview": null, "tags": [],
+        });
+        let db: Database = serde_json::from_value(serde_json::json!({
+            "root_folder": root,
+            "disabled_folder": "_Disabled",
+            "mod_strategy": "generic_rename",
+            "categories": [],
+            "mods": [record(1, "A", true), record(2, "B", true), record(3, "Gone", true)],
+            "tags": [],
+            "tag_metadata": [],
+            "profiles": [],
+        })).unwrap();
+
+        // Moving A, which the database already accounts for
+        assert!(!is_unaccounted(&db, &change("A", "removed")));
+        assert!(!is_unaccounted(&db, &change("A", "added")));
+        // Made outside: a new folder, a deleted one, and B disabled by hand
+        assert!(is_unaccounted(&db, &change("New", "added")));
+        assert!(is_unaccounted(&db, &change("Gone", "removed")));
+        assert!(is_unaccounted(&db, &change("B", "ini_renamed")));
+    }
+}
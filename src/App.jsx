@@ -1,4 +1,4 @@
-import { useEffect, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { open, save } from '@tauri-apps/plugin-dialog';
 import { listen } from '@tauri-apps/api/event';
@@ -12,7 +12,7 @@ import ManagerView from "./components/ManagerView";
 import SettingsView from "./components/SettingsView";
 import TagsView from "./components/TagsView";
 import CategoryTree from "./components/CategoryTree";
-import { PromptDialog, ConfirmDialog, DataMigrationDialog } from "./components/Dialog";
+import { PromptDialog, ConfirmDialog, DataMigrationDialog, VariantDialog } from "./components/Dialog";
 
 import "./styles/app.css";
 
@@ -56,6 +56,36 @@ function applyTheme(theme) {
   }
 }
 
+function formatBytes(bytes) {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  if (bytes < 1024 * 1024 * 1024) return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+  return `${(bytes / 1024 / 1024 / 1024).toFixed(2)} GB`;
+}
+
+// Bytes are the better measure when the total is known up front
+function jobPercent(job) {
+  const { bytes_done, bytes_total, files_done, files_total } = job.progress;
+  if (bytes_total > 0) return Math.round(bytes_done / bytes_total * 100);
+  return files_total > 0 ? Math.round(files_done / files_total * 100) : 0;
+}
+
+// Folder that actually holds the mod files. The link strategy keeps them in a
+// separate library and only links enabled mods into the game's mod folder.
+function modStorageRoot(database) {
+  return database.mod_strategy === "link_deploy" && database.library_folder
+    ? database.library_folder
+    : database.root_folder;
+}
+
+// Folder that currently contains the mod's own folder
+function modFolderRoot(database, mod) {
+  if (database.mod_strategy === "generic_rename" && !mod.enabled) {
+    return `${database.root_folder}/${database.disabled_folder || "_Disabled"}`;
+  }
+  return modStorageRoot(database);
+}
+
 export default function App() {
   // Apply theme before anything else renders
   const [themeInitialized, setThemeInitialized] = useState(false);
@@ -91,32 +121,59 @@ export default function App() {
   const [appdataPath, setAppdataPath] = useState("");
   const [localPath, setLocalPath] = useState("");
   
+  // Mod strategies registered in the backend
+  const [strategies, setStrategies] = useState([]);
+  
+  // Automatic snapshots of the current game database
+  const [backups, setBackups] = useState([]);
+  const [trash, setTrash] = useState([]);
+  
+  // Why the current mod folder isn't being watched for outside changes
+  const [watchError, setWatchError] = useState(null);
+  
+  // Keybindings and persisted variables defined by the selected mod's ini files
+  const [modKeys, setModKeys] = useState([]);
+  const [modVariables, setModVariables] = useState([]);
+  
   // Dialog states
   const [categoryPrompt, setCategoryPrompt] = useState(false);
   const [tagPrompt, setTagPrompt] = useState(false);
+  const [profilePrompt, setProfilePrompt] = useState(false);
+  const [rebindKey, setRebindKey] = useState(null);
+  const [presetPrompt, setPresetPrompt] = useState(false);
   const [deleteConfirm, setDeleteConfirm] = useState(null);
   const [migrationDialog, setMigrationDialog] = useState(null);
   const [restartDialog, setRestartDialog] = useState(false);
   const [moveToDialog, setMoveToDialog] = useState(false);
+  const [variantDialog, setVariantDialog] = useState(null);
+  const [interruptedToggle, setInterruptedToggle] = useState(null);
+  const [history, setHistory] = useState({ undo: [], redo: [] });
   
   // Progress states
   const [isProcessing, setIsProcessing] = useState(false);
   const [progressMessage, setProgressMessage] = useState("");
   const [progressPercent, setProgressPercent] = useState(0);
+  const [activeJob, setActiveJob] = useState(null);
+  // Read by the folder watcher listener, which outlives renders
+  const busyRef = useRef(false);
+  busyRef.current = isProcessing || !!activeJob;
 
   useEffect(() => {
     // Load games list on startup
     loadGames();
+    checkLegacyData();
     
     // Load paths and data location
     Promise.all([
       invoke("get_appdata_path"),
       invoke("get_local_path"),
-      invoke("get_data_location")
-    ]).then(([appdata, local, location]) => {
+      invoke("get_data_location"),
+      invoke("list_strategies")
+    ]).then(([appdata, local, location, strategyList]) => {
       setAppdataPath(appdata);
       setLocalPath(local);
       setDataLocation(location);
+      setStrategies(strategyList);
     });
 
     // Listen for progress events
@@ -131,7 +188,9 @@ export default function App() {
     });
 
     const unlistenCopy = listen('copy-progress', (event) => {
-      setProgressMessage(event.payload);
+      const { bytes_done, bytes_total, files_done, files_total } = event.payload;
+      setProgressPercent(bytes_total > 0 ? Math.round(bytes_done / bytes_total * 100) : 0);
+      setProgressMessage(`Copying files... ${files_done}/${files_total}`);
     });
 
     const unlistenCopyComplete = listen('copy-complete', () => {
@@ -139,11 +198,38 @@ export default function App() {
       setProgressMessage("");
     });
 
+    // Jobs keep running while the window reloads, so pick up any still going
+    invoke("list_jobs")
+      .then(jobs => setActiveJob(jobs.filter(job => job.state === "running").pop() || null))
+      .catch(err => console.error("Failed to list jobs:", err));
+
+    loadInterruptedToggle();
+
+    const unlistenJob = listen('job-progress', (event) => {
+      const job = event.payload;
+      if (job.state === "running") {
+        setActiveJob(job);
+      } else {
+        setActiveJob(current => (current && current.id !== job.id ? current : null));
+      }
+    });
+
+    const unlistenProfile = listen('profile-progress', (event) => {
+      const { mod_name, enable, done, total, rolling_back } = event.payload;
+      if (total === 0 || done >= total) return;
+      
+      const action = rolling_back ? "Restoring" : (enable ? "Enabling" : "Disabling");
+      setProgressPercent(Math.round(done / total * 100));
+      setProgressMessage(`${action} ${mod_name} (${done + 1}/${total})`);
+    });
+
     return () => {
       unlistenExtract.then(fn => fn());
       unlistenExtractComplete.then(fn => fn());
       unlistenCopy.then(fn => fn());
       unlistenCopyComplete.then(fn => fn());
+      unlistenProfile.then(fn => fn());
+      unlistenJob.then(fn => fn());
     };
   }, []);
 
@@ -158,6 +244,92 @@ export default function App() {
     }
   }, [currentGame]);
 
+  // Every change to the database may have been recorded as an operation
+  useEffect(() => {
+    if (!currentGame) {
+      setHistory({ undo: [], redo: [] });
+      return;
+    }
+    
+    invoke("history", { gameId: currentGame.id })
+      .then(setHistory)
+      .catch(err => console.error("Failed to load history:", err));
+  }, [currentGame, db]);
+
+  useEffect(() => {
+    if (!currentGame || view !== "manager") return;
+    
+    const handleKeyDown = (e) => {
+      if (!(e.ctrlKey || e.metaKey)) return;
+      // Text fields keep their own undo
+      if (["INPUT", "TEXTAREA", "SELECT"].includes(e.target.tagName)) return;
+      
+      const key = e.key.toLowerCase();
+      if (key === "z" && !e.shiftKey) {
+        e.preventDefault();
+        handleUndo();
+      } else if (key === "y" || (key === "z" && e.shiftKey)) {
+        e.preventDefault();
+        handleRedo();
+      }
+    };
+    
+    window.addEventListener("keydown", handleKeyDown);
+    return () => window.removeEventListener("keydown", handleKeyDown);
+  }, [currentGame, view, history]);
+
+  // Refresh the snapshot list whenever settings are opened
+  useEffect(() => {
+    if (view === "settings" && currentGame) {
+      loadBackups();
+      loadTrash();
+    }
+  }, [view, currentGame]);
+
+  // Read the selected mod's keybindings and variables for the details pane
+  const selectedModName = db?.mods.find(m => m.id === selectedModId)?.name;
+  useEffect(() => {
+    if (!currentGame || !selectedModName) {
+      setModKeys([]);
+      setModVariables([]);
+      return;
+    }
+    
+    loadModKeys(selectedModName);
+    loadModVariables(selectedModName);
+  }, [currentGame, selectedModName]);
+
+  // Keep the backend watching the current mod folder
+  useEffect(() => {
+    if (!currentGame || !db) return;
+    
+    invoke("watch_game", {
+      gameId: currentGame.id,
+      rootFolder: db.root_folder,
+      disabledFolder: db.disabled_folder || "_Disabled"
+    }).then(() => setWatchError(null)).catch(err => setWatchError(String(err)));
+  }, [currentGame, db?.root_folder, db?.disabled_folder]);
+
+  // Re-check the mod folder when it changes outside the manager
+  useEffect(() => {
+    if (!currentGame) return;
+    
+    let timer = null;
+    const unlistenFolder = listen('mod-folder-changed', (event) => {
+      // An import in progress adds its folder before its record
+      if (event.payload.game_id !== currentGame.id || busyRef.current) return;
+      clearTimeout(timer);
+      timer = setTimeout(() => {
+        if (!busyRef.current) handleScanModFolder(true);
+      }, 1500);
+    });
+    
+    return () => {
+      clearTimeout(timer);
+      unlistenFolder.then(fn => fn());
+    };
+  }, [currentGame]);
+
   async function loadGames() {
     try {
       const loadedGames = await invoke("load_games");
@@ -168,6 +340,126 @@ export default function App() {
     }
   }
 
+  async function checkLegacyData() {
+    try {
+      if (!(await invoke("has_legacy_db"))) return;
+      
+      setDeleteConfirm({
+        title: "Import Existing Mods?",
+        message: "Mod data from an older version of the manager was found. Import it as a new game? Its categories, mods, tags and strategy are kept.",
+        confirmText: "Import",
+        cancelText: "Not Now",
+        onConfirm: async () => {
+          setDeleteConfirm(null);
+          try {
+            await invoke("import_legacy_db", {
+              name: "Wuthering Waves",
+              description: "Imported from an older version of the manager"
+            });
+            await loadGames();
+          } catch (err) {
+            alert("Failed to import legacy data: " + err);
+          }
+        }
+      });
+    } catch (err) {
+      console.error("Failed to check for legacy data:", err);
+    }
+  }
+
+  async function loadModKeys(name) {
+    try {
+      setModKeys(await invoke("list_mod_keys", { gameId: currentGame.id, name }));
+    } catch (err) {
+      setModKeys([]);
+    }
+  }
+
+  async function loadModVariables(name) {
+    try {
+      setModVariables(await invoke("get_mod_variables", { gameId: currentGame.id, name }));
+    } catch (err) {
+      setModVariables([]);
+    }
+  }
+
+  async function handleSetVariable(variable, value) {
+    try {
+      await invoke("set_mod_variables", {
+        gameId: currentGame.id,
+        name: selectedMod.name,
+        values: [{ file: variable.file, name: variable.name, value }]
+      });
+    } catch (err) {
+      alert("Failed to set variable: " + err);
+    }
+    await loadModVariables(selectedMod.name);
+  }
+
+  async function handleApplyPreset(preset) {
+    try {
+      await invoke("apply_mod_preset", { gameId: currentGame.id, name: selectedMod.name, preset });
+      await loadModVariables(selectedMod.name);
+    } catch (err) {
+      alert("Failed to apply preset: " + err);
+    }
+  }
+
+  async function handlePresetConfirm(preset) {
+    setPresetPrompt(false);
+    try {
+      await invoke("save_mod_preset", { gameId: currentGame.id, name: selectedMod.name, preset });
+      await loadGameData(currentGame.id);
+    } catch (err) {
+      alert("Failed to save preset: " + err);
+    }
+  }
+
+  function handleDeletePreset(preset) {
+    persist({
+      ...db,
+      mods: db.mods.map(m =>
+        m.id === selectedModId ? { ...m, presets: m.presets.filter(p => p.name !== preset) } : m
+      )
+    });
+  }
+
+  async function handleRebindConfirm(newKey) {
+    const binding = rebindKey;
+    setRebindKey(null);
+    
+    try {
+      await invoke("rebind_mod_key", {
+        root: modFolderRoot(db, selectedMod),
+        name: selectedMod.name,
+        section: binding.section,
+        newKey,
+        file: binding.file
+      });
+      await loadModKeys(selectedMod.name);
+    } catch (err) {
+      alert("Failed to change key: " + err);
+    }
+  }
+
+  async function loadBackups() {
+    try {
+      setBackups(await invoke("list_backups", { gameId: currentGame.id }));
+    } catch (err) {
+      console.error("Failed to load backups:", err);
+      setBackups([]);
+    }
+  }
+
+  async function loadTrash() {
+    try {
+      setTrash(await invoke("list_trash", { gameId: currentGame.id }));
+    } catch (err) {
+      console.error("Failed to load recycle bin:", err);
+      setTrash([]);
+    }
+  }
+
   async function loadGameData(gameId) {
     try {
       const loadedDb = await invoke("load_game_db", { gameId });
@@ -177,7 +469,7 @@ export default function App() {
         loadedDb.mods.map(async (mod) => {
           try {
             const preview = await invoke("load_preview", {
-              root: loadedDb.root_folder,
+              root: modStorageRoot(loadedDb),
               name: mod.name
             });
             return { ...mod, preview };
@@ -284,7 +576,8 @@ export default function App() {
   function persist(updated) {
     setDb(updated);
     if (currentGame) {
-      invoke("save_game_db", { gameId: currentGame.id, db: updated });
+      invoke("save_game_db", { gameId: currentGame.id, db: updated })
+        .catch(err => alert("Failed to save: " + err));
     }
   }
 
@@ -346,20 +639,114 @@ export default function App() {
     });
   }
 
+  // The backend records toggles made with a game id itself, so only the
+  // local copy needs updating.
+  function applyToggleChanges(changes) {
+    setDb(prev => ({
+      ...prev,
+      mods: prev.mods.map(m => {
+        const change = changes.find(c => c.name === m.name);
+        return change ? { ...m, enabled: change.enabled } : m;
+      })
+    }));
+  }
+
+  // A failed bulk toggle is rolled back, so the first error is the cause and
+  // any others are mods that could not be put back
+  function reportToggleErrors(report, action) {
+    const [cause, ...unrestored] = report.errors;
+    if (!cause) return;
+    
+    if (cause !== "Cancelled" || unrestored.length > 0) {
+      let message = cause === "Cancelled" ? `Cancelled, no mods were ${action}.` : `No mods were ${action}:\n${cause}`;
+      if (unrestored.length > 0) {
+        message += "\n\nSome mods could not be restored:\n" + unrestored.join("\n");
+      }
+      alert(message);
+    }
+    
+    // The backend recorded what is actually on disk
+    if (unrestored.length > 0 && currentGame) {
+      loadGameData(currentGame.id);
+    }
+  }
+
+  async function runHistory(command) {
+    const gameId = currentGame.id;
+    const pending = command === "undo" ? history.undo : history.redo;
+    if (pending.length === 0) return;
+    
+    try {
+      await invoke(command, { gameId });
+    } catch (err) {
+      // An operation that can no longer be reversed blocks everything before it
+      setDeleteConfirm({
+        title: command === "undo" ? "Undo Failed" : "Redo Failed",
+        message: `${err}. Clear the history so later changes can still be undone?`,
+        confirmText: "Clear History",
+        onConfirm: async () => {
+          setDeleteConfirm(null);
+          await invoke("clear_history", { gameId }).catch(err => alert("Failed to clear history: " + err));
+          loadGameData(gameId);
+        }
+      });
+      return;
+    }
+    
+    loadGameData(gameId);
+  }
+
+  function handleUndo() {
+    runHistory("undo");
+  }
+
+  function handleRedo() {
+    runHistory("redo");
+  }
+
+  // Bulk toggles cut short by a crash are finished or undone one at a time
+  function loadInterruptedToggle() {
+    invoke("list_journals")
+      .then(journals => setInterruptedToggle(journals[0] || null))
+      .catch(err => console.error("Failed to list interrupted toggles:", err));
+  }
+
+  async function handleInterruptedToggle(finish) {
+    const journal = interruptedToggle;
+    setInterruptedToggle(null);
+    
+    try {
+      if (finish) {
+        const report = await invoke("resume_journal", { id: journal.id });
+        reportToggleErrors(report, journal.enable ? "enabled" : "disabled");
+      } else {
+        const unrestored = await invoke("revert_journal", { id: journal.id });
+        if (unrestored.length > 0) {
+          alert("Some mods could not be restored:\n" + unrestored.join("\n"));
+        }
+      }
+    } catch (err) {
+      alert("Failed to recover interrupted toggle: " + err);
+    }
+    
+    if (currentGame && currentGame.id === journal.game_id) {
+      loadGameData(currentGame.id);
+    }
+    loadInterruptedToggle();
+  }
+
   function toggleMod(mod) {
     invoke("toggle_mod", {
       root: db.root_folder,
       name: mod.name,
       enable: !mod.enabled,
       strategy: db.mod_strategy,
-      disabledFolder: db.disabled_folder || "_Disabled"
-    });
-
-    persist({
-      ...db,
-      mods: db.mods.map(m =>
-        m.id === mod.id ? { ...m, enabled: !m.enabled } : m
-      )
+      disabledFolder: db.disabled_folder || "_Disabled",
+      libraryFolder: db.library_folder || null,
+      gameId: currentGame.id
+    }).then(applyToggleChanges).catch(err => {
+      alert("Failed to toggle mod: " + err);
+      loadGameData(currentGame.id);
     });
   }
 
@@ -374,14 +761,14 @@ export default function App() {
         title: "Select Mod Folder or Archive",
         filters: [{
           name: 'Mod Files',
-          extensions: ['zip', '7z', 'rar']
+          extensions: ['zip', '7z', 'rar', 'tar', 'gz', 'tgz', 'xz', 'txz']
         }]
       });
 
       if (!selected) return;
 
       // Check if it's an archive file
-      const isArchive = selected.toLowerCase().match(/\.(zip|7z|rar)$/);
+      const isArchive = selected.toLowerCase().match(/\.(zip|7z|rar|tar|tar\.gz|tgz|tar\.xz|txz)$/);
       
       // Extract folder name from path
       const pathParts = selected.split(/[\\/]/);
@@ -389,7 +776,16 @@ export default function App() {
       
       // Remove archive extension if present
       if (isArchive) {
-        folderName = folderName.replace(/\.(zip|7z|rar)$/i, '');
+        folderName = folderName.replace(/\.(zip|7z|rar|tar|tar\.gz|tgz|tar\.xz|txz)$/i, '');
+      }
+
+      // Archives shipping several mod folders let the user pick what to import
+      if (isArchive) {
+        const variants = await invoke("inspect_archive", { archivePath: selected });
+        if (variants.length > 0) {
+          setVariantDialog({ archivePath: selected, folderName, variants });
+          return;
+        }
       }
 
       // Check if mod already exists
@@ -398,59 +794,151 @@ export default function App() {
         return;
       }
 
-      // Show processing indicator
-      setIsProcessing(true);
-      setProgressMessage(isArchive ? "Extracting archive..." : "Moving folder...");
-      setProgressPercent(0);
+      await installMod(selected, folderName, !!isArchive, null);
+    } catch (err) {
+      setIsProcessing(false);
+      console.error("Failed to add mod:", err);
+      alert("Failed to add mod: " + err);
+    }
+  }
 
-      // Handle archive extraction or folder move
-      try {
-        if (isArchive) {
-          await invoke("extract_archive", {
-            archivePath: selected,
-            destRoot: db.root_folder,
-            destName: folderName
-          });
-        } else {
-          // Move folder
-          await invoke("move_mod", {
-            source: selected,
-            destRoot: db.root_folder,
-            destName: folderName
-          });
-        }
-      } catch (err) {
-        setIsProcessing(false);
+  // Extracts or moves a mod into place and adds it to the database. With
+  // `variants`, only those folders of the archive are installed, tagged with a
+  // mutually exclusive tag named after the archive.
+  async function installMod(source, folderName, isArchive, variants) {
+    // Show processing indicator
+    setIsProcessing(true);
+    setProgressMessage(isArchive ? "Extracting archive..." : "Moving folder...");
+    setProgressPercent(0);
+
+    const now = Date.now();
+    const tag = variants ? folderName : null;
+    const newRecord = (name, i) => ({
+      id: now + i,
+      name,
+      category_id: selectedCategory,
+      enabled: !variants || i === 0,
+      notes: "",
+      preview: null,
+      tags: tag ? [tag] : [],
+      presets: []
+    });
+
+    // Handle archive extraction or folder move. Archives bundling several
+    // mods come back as several names.
+    let names = [folderName];
+    try {
+      if (isArchive) {
+        names = await invoke("extract_archive", {
+          archivePath: source,
+          destRoot: modStorageRoot(db),
+          destName: folderName,
+          variants
+        });
+      } else {
+        // Move folder. The backend adds its record, so undoing the move
+        // removes the record too.
+        await invoke("move_mod", {
+          source,
+          destRoot: modStorageRoot(db),
+          destName: folderName,
+          gameId: currentGame.id,
+          record: newRecord(folderName, 0)
+        });
+      }
+      
+      // New mods land enabled in the mod folder but only show up from the
+      // library once linked in. Only the first variant starts enabled.
+      const landsEnabled = db.mod_strategy !== "link_deploy";
+      for (const [i, name] of names.entries()) {
+        const enable = !variants || i === 0;
+        if (enable === landsEnabled) continue;
+        
+        await invoke("toggle_mod", {
+          root: db.root_folder,
+          name,
+          enable,
+          strategy: db.mod_strategy,
+          disabledFolder: db.disabled_folder || "_Disabled",
+          libraryFolder: db.library_folder || null
+        });
+      }
+    } catch (err) {
+      setIsProcessing(false);
+      if (err !== "Cancelled") {
         alert("Failed to " + (isArchive ? "extract" : "move") + " mod: " + err);
-        return;
       }
+      return;
+    }
 
-      setIsProcessing(false);
-      setProgressMessage("");
+    setIsProcessing(false);
+    setProgressMessage("");
 
-      // Add to database
-      persist({
-        ...db,
-        mods: [
-          ...db.mods,
-          {
-            id: Date.now(),
-            name: folderName,
-            category_id: selectedCategory,
-            enabled: true,
-            notes: "",
-            preview: null,
-            tags: []
-          }
-        ]
-      });
+    let tags = db.tags || [];
+    let tagMetadata = db.tag_metadata || [];
+    if (tag) {
+      if (!tags.includes(tag)) tags = [...tags, tag];
+      const existing = tagMetadata.find(tm => tm.name === tag);
+      tagMetadata = [
+        ...tagMetadata.filter(tm => tm.name !== tag),
+        {
+          name: tag,
+          description: existing?.description || `Variants of ${folderName}`,
+          preview: existing?.preview || null,
+          mutually_exclusive: true,
+          exclusive_mode: existing?.exclusive_mode || "auto_disable"
+        }
+      ];
+    }
+
+    // Add to database
+    persist({
+      ...db,
+      tags,
+      tag_metadata: tagMetadata,
+      mods: [...db.mods, ...names.map(newRecord)]
+    });
+
+    if (names.length > 1 && !variants) {
+      alert(`Imported ${names.length} mods from the archive:\n${names.join("\n")}`);
+    }
+  }
+
+  async function handleVariantConfirm(selectedVariants) {
+    const { archivePath, folderName } = variantDialog;
+    setVariantDialog(null);
+    
+    const names = selectedVariants ? selectedVariants.map(v => `${folderName} - ${v}`) : [];
+    const taken = names.filter(name => db.mods.some(m => m.name === name));
+    if (taken.length > 0) {
+      alert("A mod with this name already exists: " + taken.join(", "));
+      return;
+    }
+    
+    try {
+      await installMod(archivePath, folderName, true, selectedVariants);
     } catch (err) {
       setIsProcessing(false);
-      console.error("Failed to add mod:", err);
       alert("Failed to add mod: " + err);
     }
   }
 
+  // Removes the links of enabled library mods so deleting or renaming the
+  // library folder doesn't leave dangling links in the game's mod folder.
+  async function unlinkLibraryMods(mods) {
+    const linked = mods.filter(m => m.enabled).map(m => m.name);
+    if (db.mod_strategy !== "link_deploy" || linked.length === 0) return;
+    
+    await invoke("toggle_mods_bulk", {
+      root: db.root_folder,
+      modNames: linked,
+      enable: false,
+      strategy: db.mod_strategy,
+      disabledFolder: db.disabled_folder || "_Disabled",
+      libraryFolder: db.library_folder || null
+    });
+  }
+
   function deleteMod() {
     if (!selectedModId) return;
     
@@ -458,20 +946,31 @@ export default function App() {
     
     setDeleteConfirm({
       title: "Delete Mod",
-      message: `Delete mod "${mod.name}" permanently?`,
-      onConfirm: () => {
-        invoke("delete_mod", {
-          root: db.root_folder,
-          name: mod.name
-        }).catch(err => alert("Error deleting mod: " + err));
+      message: `Move mod "${mod.name}" to the recycle bin?`,
+      onConfirm: async () => {
+        setDeleteConfirm(null);
+        
+        // The record is read from the database into the recycle bin, so it
+        // has to be deleted on disk before it is dropped here
+        try {
+          await unlinkLibraryMods([mod]);
+          await invoke("delete_mod", {
+            root: modStorageRoot(db),
+            name: mod.name,
+            gameId: currentGame.id
+          });
+        } catch (err) {
+          alert("Error deleting mod: " + err);
+          loadGameData(currentGame.id);
+          return;
+        }
 
         persist({
           ...db,
-          mods: db.mods.filter(m => m.id !== selectedModId)
+          mods: db.mods.filter(m => m.id !== mod.id)
         });
 
         setSelectedModId(null);
-        setDeleteConfirm(null);
       }
     });
   }
@@ -482,7 +981,7 @@ export default function App() {
     const mod = db.mods.find(m => m.id === selectedModId);
     
     invoke("save_notes", {
-      root: db.root_folder,
+      root: modStorageRoot(db),
       name: mod.name,
       notes: text
     }).catch(err => console.error("Failed to save notes:", err));
@@ -503,7 +1002,7 @@ export default function App() {
     const mod = db.mods.find(m => m.id === selectedModId);
 
     invoke("save_preview", {
-      root: db.root_folder,
+      root: modStorageRoot(db),
       name: mod.name,
       data: dataUrl
     }).catch(err => alert("Error saving preview: " + err));
@@ -562,28 +1061,23 @@ export default function App() {
       .map(m => m.name);
     
     try {
-      const errors = await invoke("toggle_mods_bulk", {
+      const report = await invoke("toggle_mods_bulk", {
         root: db.root_folder,
         modNames: modNames,
         enable: true,
         strategy: db.mod_strategy,
-        disabledFolder: db.disabled_folder || "_Disabled"
+        disabledFolder: db.disabled_folder || "_Disabled",
+        libraryFolder: db.library_folder || null,
+        gameId: currentGame.id
       });
       
-      if (errors.length > 0) {
-        alert("Some mods failed to enable:\n" + errors.join("\n"));
-      }
-      
-      persist({
-        ...db,
-        mods: db.mods.map(m =>
-          selectedModIds.includes(m.id) ? { ...m, enabled: true } : m
-        )
-      });
+      reportToggleErrors(report, "enabled");
       
+      applyToggleChanges(report.changes);
       setSelectedModIds([]);
     } catch (err) {
       alert("Bulk enable failed: " + err);
+      loadGameData(currentGame.id);
     }
   }
 
@@ -595,28 +1089,23 @@ export default function App() {
       .map(m => m.name);
     
     try {
-      const errors = await invoke("toggle_mods_bulk", {
+      const report = await invoke("toggle_mods_bulk", {
         root: db.root_folder,
         modNames: modNames,
         enable: false,
         strategy: db.mod_strategy,
-        disabledFolder: db.disabled_folder || "_Disabled"
+        disabledFolder: db.disabled_folder || "_Disabled",
+        libraryFolder: db.library_folder || null,
+        gameId: currentGame.id
       });
       
-      if (errors.length > 0) {
-        alert("Some mods failed to disable:\n" + errors.join("\n"));
-      }
-      
-      persist({
-        ...db,
-        mods: db.mods.map(m =>
-          selectedModIds.includes(m.id) ? { ...m, enabled: false } : m
-        )
-      });
+      reportToggleErrors(report, "disabled");
       
+      applyToggleChanges(report.changes);
       setSelectedModIds([]);
     } catch (err) {
       alert("Bulk disable failed: " + err);
+      loadGameData(currentGame.id);
     }
   }
 
@@ -625,16 +1114,17 @@ export default function App() {
     
     setDeleteConfirm({
       title: "Delete Multiple Mods",
-      message: `Delete ${selectedModIds.length} mods permanently?`,
+      message: `Move ${selectedModIds.length} mods to the recycle bin?`,
       onConfirm: async () => {
-        const modNames = db.mods
-          .filter(m => selectedModIds.includes(m.id))
-          .map(m => m.name);
+        const selectedMods = db.mods.filter(m => selectedModIds.includes(m.id));
+        const modNames = selectedMods.map(m => m.name);
         
         try {
+          await unlinkLibraryMods(selectedMods);
           const errors = await invoke("delete_mods_bulk", {
-            root: db.root_folder,
-            modNames: modNames
+            root: modStorageRoot(db),
+            modNames: modNames,
+            gameId: currentGame.id
           });
           
           if (errors.length > 0) {
@@ -694,11 +1184,21 @@ export default function App() {
       return;
     }
     
-    invoke("rename_mod", {
-      root: db.root_folder,
+    const relink = db.mod_strategy === "link_deploy" && mod.enabled;
+    
+    unlinkLibraryMods([mod]).then(() => invoke("rename_mod", {
+      root: modStorageRoot(db),
       oldName: mod.name,
-      newName: newName
-    }).then(() => {
+      newName: newName,
+      gameId: currentGame.id
+    })).then(() => relink && invoke("toggle_mod", {
+      root: db.root_folder,
+      name: newName,
+      enable: true,
+      strategy: db.mod_strategy,
+      disabledFolder: db.disabled_folder || "_Disabled",
+      libraryFolder: db.library_folder || null
+    })).then(() => {
       persist({
         ...db,
         mods: db.mods.map(m =>
@@ -707,6 +1207,7 @@ export default function App() {
       });
     }).catch(err => {
       alert("Failed to rename mod: " + err);
+      loadGameData(currentGame.id);
     });
   }
 
@@ -755,20 +1256,16 @@ export default function App() {
       modNames: modNames,
       enable: true,
       strategy: db.mod_strategy,
-      disabledFolder: db.disabled_folder || "_Disabled"
-    }).then(errors => {
-      if (errors && errors.length > 0) {
-        alert("Some mods failed to enable:\n" + errors.join("\n"));
-      }
+      disabledFolder: db.disabled_folder || "_Disabled",
+      libraryFolder: db.library_folder || null,
+      gameId: currentGame.id
+    }).then(report => {
+      reportToggleErrors(report, "enabled");
       
-      persist({
-        ...db,
-        mods: db.mods.map(m =>
-          modsWithTag.find(mt => mt.id === m.id) ? { ...m, enabled: true } : m
-        )
-      });
+      applyToggleChanges(report.changes);
     }).catch(err => {
       alert("Failed to enable mods: " + err);
+      loadGameData(currentGame.id);
     });
   }
 
@@ -783,20 +1280,16 @@ export default function App() {
       modNames: modNames,
       enable: false,
       strategy: db.mod_strategy,
-      disabledFolder: db.disabled_folder || "_Disabled"
-    }).then(errors => {
-      if (errors && errors.length > 0) {
-        alert("Some mods failed to disable:\n" + errors.join("\n"));
-      }
+      disabledFolder: db.disabled_folder || "_Disabled",
+      libraryFolder: db.library_folder || null,
+      gameId: currentGame.id
+    }).then(report => {
+      reportToggleErrors(report, "disabled");
       
-      persist({
-        ...db,
-        mods: db.mods.map(m =>
-          modsWithTag.find(mt => mt.id === m.id) ? { ...m, enabled: false } : m
-        )
-      });
+      applyToggleChanges(report.changes);
     }).catch(err => {
       alert("Failed to disable mods: " + err);
+      loadGameData(currentGame.id);
     });
   }
 
@@ -870,6 +1363,205 @@ export default function App() {
     setSelectedModIds([]);
   }
 
+  async function handleDetectConflicts() {
+    if (!currentGame) return;
+    
+    try {
+      const report = await invoke("detect_conflicts", { gameId: currentGame.id });
+      
+      const lines = [];
+      
+      if (report.hashes.length > 0) {
+        lines.push("Enabled mods overriding the same hash:");
+        report.hashes.forEach(conflict => {
+          const mods = [...new Set(conflict.users.map(u => u.mod_name))];
+          const index = conflict.match_first_index ? ` (index ${conflict.match_first_index})` : "";
+          lines.push(`${conflict.hash}${index}: ${mods.join(", ")}`);
+        });
+      }
+      
+      if (report.keys.length > 0) {
+        if (lines.length > 0) lines.push("");
+        lines.push("Enabled mods listening for the same key:");
+        report.keys.forEach(conflict => {
+          const users = conflict.users.map(u => `${u.mod_name} (${u.binding.combo.label}, ${u.binding.description})`);
+          lines.push(`${conflict.key}: ${users.join(", ")}`);
+        });
+      }
+      
+      if (lines.length === 0) {
+        lines.push("No conflicts between enabled mods.");
+      }
+      
+      if (report.errors.length > 0) {
+        lines.push("", "Could not read:", ...report.errors);
+      }
+      
+      alert(lines.join("\n"));
+    } catch (err) {
+      alert("Failed to check for conflicts: " + err);
+    }
+  }
+
+  async function handleScanModFolder(quiet = false) {
+    if (!currentGame) return;
+    
+    try {
+      const report = await invoke("scan_mod_folder", { gameId: currentGame.id, applyFixes: false });
+      
+      const problems = [];
+      if (report.untracked.length > 0) {
+        problems.push(`Untracked folders: ${report.untracked.join(", ")}.`);
+      }
+      if (report.missing.length > 0) {
+        problems.push(`Missing folders: ${report.missing.map(m => m.name).join(", ")}.`);
+      }
+      if (report.state_mismatches.length > 0) {
+        problems.push(`Enabled state out of sync: ${report.state_mismatches.map(m => m.name).join(", ")}.`);
+      }
+      
+      if (problems.length === 0) {
+        if (!quiet) alert("The database matches the mod folder.");
+        return;
+      }
+      
+      setDeleteConfirm({
+        title: "Mod Folder Out of Sync",
+        message: `${problems.join(" ")} Add untracked folders, remove missing mods and update enabled states?`,
+        confirmText: "Apply Fixes",
+        onConfirm: async () => {
+          setDeleteConfirm(null);
+          try {
+            await invoke("scan_mod_folder", { gameId: currentGame.id, applyFixes: true });
+            await loadGameData(currentGame.id);
+          } catch (err) {
+            alert("Failed to apply fixes: " + err);
+          }
+        }
+      });
+    } catch (err) {
+      if (quiet) {
+        console.error("Failed to scan mod folder:", err);
+      } else {
+        alert("Failed to scan mod folder: " + err);
+      }
+    }
+  }
+
+  async function handleCompareBackup(fileName) {
+    try {
+      const diff = await invoke("diff_backup", { gameId: currentGame.id, fileName });
+      
+      const lines = [];
+      if (diff.added.length > 0) {
+        lines.push(`Added since backup: ${diff.added.map(m => m.name).join(", ")}`);
+      }
+      if (diff.removed.length > 0) {
+        lines.push(`Removed since backup: ${diff.removed.map(m => m.name).join(", ")}`);
+      }
+      if (diff.enabled_changed.length > 0) {
+        lines.push(`Toggled since backup: ${diff.enabled_changed.map(m => m.name).join(", ")}`);
+      }
+      
+      alert(lines.length > 0 ? lines.join("\n") : "No mod changes since this backup.");
+    } catch (err) {
+      alert("Failed to compare backup: " + err);
+    }
+  }
+
+  async function handleApplyProfile(name) {
+    setIsProcessing(true);
+    setProgressMessage(`Applying profile "${name}"...`);
+    setProgressPercent(0);
+    
+    try {
+      await invoke("apply_profile", { gameId: currentGame.id, profile: name });
+    } catch (err) {
+      alert("Failed to apply profile: " + err);
+    } finally {
+      setIsProcessing(false);
+      setProgressMessage("");
+      setProgressPercent(0);
+    }
+    
+    // Even a failed apply may have switched mods or restored some variables
+    await loadGameData(currentGame.id);
+  }
+
+  async function handleProfileConfirm(name) {
+    setProfilePrompt(false);
+    
+    try {
+      await invoke("save_profile", { gameId: currentGame.id, name });
+      await loadGameData(currentGame.id);
+    } catch (err) {
+      alert("Failed to save profile: " + err);
+    }
+  }
+
+  function handleDeleteProfile(name) {
+    setDeleteConfirm({
+      title: "Delete Profile",
+      message: `Delete profile "${name}"? Mods are not changed.`,
+      onConfirm: () => {
+        setDeleteConfirm(null);
+        persist({
+          ...db,
+          profiles: db.profiles.filter(p => p.name !== name)
+        });
+      }
+    });
+  }
+
+  function handleRestoreBackup(fileName) {
+    setDeleteConfirm({
+      title: "Restore Backup",
+      message: "Replace the current database with this backup? Mod folders on disk are not changed; use Scan Mod Folder afterwards if needed.",
+      confirmText: "Restore",
+      onConfirm: async () => {
+        setDeleteConfirm(null);
+        try {
+          await invoke("restore_backup", { gameId: currentGame.id, fileName });
+          await loadGameData(currentGame.id);
+          await loadBackups();
+        } catch (err) {
+          alert("Failed to restore backup: " + err);
+        }
+      }
+    });
+  }
+
+  async function handleRestoreFromTrash(id) {
+    try {
+      await invoke("restore_from_trash", { id });
+      await loadGameData(currentGame.id);
+      await loadTrash();
+    } catch (err) {
+      alert("Failed to restore mod: " + err);
+    }
+  }
+
+  function handlePurgeTrash(olderThanDays) {
+    const cutoff = olderThanDays ? Date.now() - olderThanDays * 24 * 60 * 60 * 1000 : null;
+    
+    setDeleteConfirm({
+      title: "Empty Recycle Bin",
+      message: olderThanDays
+        ? `Permanently delete mods that have been in the recycle bin for more than ${olderThanDays} days? This covers every game.`
+        : "Permanently delete every mod in the recycle bin? This covers every game.",
+      confirmText: "Delete",
+      onConfirm: async () => {
+        setDeleteConfirm(null);
+        try {
+          await invoke("purge_trash", { olderThan: cutoff });
+          await loadTrash();
+        } catch (err) {
+          alert("Failed to empty recycle bin: " + err);
+        }
+      }
+    });
+  }
+
   function changeModStrategy(strategy) {
     persist({
       ...db,
@@ -926,7 +1618,8 @@ export default function App() {
         categories: db.categories,
         mods: db.mods,
         tags: db.tags,
-        tag_metadata: db.tag_metadata || []
+        tag_metadata: db.tag_metadata || [],
+        profiles: db.profiles || []
       };
 
       await invoke("export_config", {
@@ -971,7 +1664,8 @@ export default function App() {
               categories: imported.categories || db.categories,
               mods: imported.mods || db.mods,
               tags: imported.tags || db.tags,
-              tag_metadata: imported.tag_metadata || db.tag_metadata || []
+              tag_metadata: imported.tag_metadata || db.tag_metadata || [],
+              profiles: imported.profiles || db.profiles
             });
 
             alert("Configuration imported successfully!");
@@ -1035,7 +1729,7 @@ export default function App() {
       const { from, to } = migrationDialog;
       
       if (keepLocation === from) {
-        // Keep old location data, overwrite new location
+        // Keep old location data, replacing the data at the new location
         await invoke("migrate_data", {
           from: from,
           to: to,
@@ -1043,35 +1737,10 @@ export default function App() {
           createBackup: createBackup
         });
       } else {
-        // Keep new location data, delete old (new becomes source in migration)
-        await invoke("migrate_data", {
-          from: to,
-          to: to,
-          deleteOld: false,
-          createBackup: false
-        });
-        
-        // Now delete old location with optional backup
-        if (createBackup) {
-          // Create backup before deleting
-          const oldPath = from === "appdata" 
-            ? await invoke("get_appdata_path") 
-            : await invoke("get_local_path");
-          // Backup will be created by migrate_data when deleteOld is true
-          await invoke("migrate_data", {
-            from: from,
-            to: from + "-temp",
-            deleteOld: false,
-            createBackup: true
-          });
-        }
-        
-        // Delete old location file
-        await invoke("migrate_data", {
-          from: from,
-          to: from,
-          deleteOld: true,
-          createBackup: false
+        // Keep new location data, discard the old location
+        await invoke("discard_data", {
+          location: from,
+          createBackup: createBackup
         });
       }
       
@@ -1120,6 +1789,13 @@ export default function App() {
             mods={db.mods}
             selectedCategory={selectedCategory}
             selectedMod={selectedMod}
+            modKeys={modKeys}
+            onRebindKey={setRebindKey}
+            modVariables={modVariables}
+            onSetVariable={handleSetVariable}
+            onApplyPreset={handleApplyPreset}
+            onSavePreset={() => setPresetPrompt(true)}
+            onDeletePreset={handleDeletePreset}
             selectedModIds={selectedModIds}
             searchQuery={searchQuery}
             onSelectCategory={setSelectedCategory}
@@ -1143,6 +1819,9 @@ export default function App() {
             onUpdateName={updateName}
             onMoveModToCategory={moveModToCategory}
             onSearchChange={setSearchQuery}
+            history={history}
+            onUndo={handleUndo}
+            onRedo={handleRedo}
           />
         )}
 
@@ -1167,8 +1846,11 @@ export default function App() {
         {view === "settings" && (
           <SettingsView
             rootFolder={db?.root_folder || ""}
+            watchError={watchError}
             modStrategy={db?.mod_strategy || "wuthering_waves"}
+            strategies={strategies}
             disabledFolder={db?.disabled_folder || "_Disabled"}
+            libraryFolder={db?.library_folder || ""}
             dataLocation={dataLocation}
             appdataPath={appdataPath}
             localPath={localPath}
@@ -1181,7 +1863,22 @@ export default function App() {
             onChangeDisabledFolder={(value) =>
               db && persist({ ...db, disabled_folder: value })
             }
+            onChangeLibraryFolder={(value) =>
+              db && persist({ ...db, library_folder: value })
+            }
             onChangeDataLocation={changeDataLocation}
+            onScanModFolder={() => handleScanModFolder()}
+            onDetectConflicts={handleDetectConflicts}
+            backups={backups}
+            onCompareBackup={handleCompareBackup}
+            onRestoreBackup={handleRestoreBackup}
+            trash={trash}
+            onRestoreFromTrash={handleRestoreFromTrash}
+            onPurgeTrash={handlePurgeTrash}
+            profiles={db?.profiles || []}
+            onApplyProfile={handleApplyProfile}
+            onSaveProfile={() => setProfilePrompt(true)}
+            onDeleteProfile={handleDeleteProfile}
             onExportConfig={handleExportConfig}
             onImportConfig={handleImportConfig}
           />
@@ -1212,6 +1909,30 @@ export default function App() {
         onCancel={() => setTagPrompt(false)}
       />
 
+      <PromptDialog
+        isOpen={!!rebindKey}
+        title={`Change Key: ${rebindKey?.description || ""} (${rebindKey?.combo.label || ""})`}
+        placeholder="New key, e.g. VK_F5, ctrl 7 or no_modifiers x"
+        onConfirm={handleRebindConfirm}
+        onCancel={() => setRebindKey(null)}
+      />
+
+      <PromptDialog
+        isOpen={presetPrompt}
+        title="Save Variable Preset"
+        placeholder="Preset name (an existing name is overwritten)"
+        onConfirm={handlePresetConfirm}
+        onCancel={() => setPresetPrompt(false)}
+      />
+
+      <PromptDialog
+        isOpen={profilePrompt}
+        title="Save Profile"
+        placeholder="Profile name (an existing name is overwritten)"
+        onConfirm={handleProfileConfirm}
+        onCancel={() => setProfilePrompt(false)}
+      />
+
       <ConfirmDialog
         isOpen={!!deleteConfirm}
         title={deleteConfirm?.title}
@@ -1222,6 +1943,24 @@ export default function App() {
         cancelText={deleteConfirm?.cancelText}
       />
 
+      <ConfirmDialog
+        isOpen={!!interruptedToggle}
+        title="Interrupted Toggle"
+        message={interruptedToggle && `${interruptedToggle.enable ? "Enabling" : "Disabling"} ${interruptedToggle.mods.length} mods was interrupted after ${interruptedToggle.next} of them. Finish the toggle, or undo the changes it already made?`}
+        onConfirm={() => handleInterruptedToggle(true)}
+        onCancel={() => handleInterruptedToggle(false)}
+        confirmText="Finish"
+        cancelText="Undo"
+      />
+
+      <VariantDialog
+        isOpen={!!variantDialog}
+        archiveName={variantDialog?.folderName}
+        variants={variantDialog?.variants || []}
+        onConfirm={handleVariantConfirm}
+        onCancel={() => setVariantDialog(null)}
+      />
+
       <DataMigrationDialog
         isOpen={!!migrationDialog}
         fromLocation={migrationDialog?.from}
@@ -1269,16 +2008,36 @@ export default function App() {
         </div>
       )}
 
-      {isProcessing && (
+      {(isProcessing || activeJob) && (
         <div className="progress-overlay">
           <div className="progress-dialog">
             <div className="progress-spinner"></div>
-            <div className="progress-text">{progressMessage}</div>
-            {progressPercent > 0 && (
+            <div className="progress-text">{progressMessage || activeJob?.label}</div>
+            {activeJob && (
+              <div className="progress-detail">
+                {activeJob.progress.current_path && <div>{activeJob.progress.current_path}</div>}
+                <div>
+                  {activeJob.progress.files_done}/{activeJob.progress.files_total || "?"}
+                  {activeJob.progress.bytes_total > 0 &&
+                    ` · ${formatBytes(activeJob.progress.bytes_done)} of ${formatBytes(activeJob.progress.bytes_total)}`}
+                  {activeJob.progress.bytes_per_second > 0 &&
+                    ` · ${formatBytes(activeJob.progress.bytes_per_second)}/s`}
+                </div>
+              </div>
+            )}
+            {(progressPercent > 0 || activeJob?.progress.files_total > 0) && (
               <div className="progress-bar">
-                <div className="progress-fill" style={{ width: `${progressPercent}%` }}></div>
+                <div className="progress-fill" style={{ width: `${activeJob ? jobPercent(activeJob) : progressPercent}%` }}></div>
               </div>
             )}
+            {activeJob && (
+              <button
+                className="secondary-button progress-cancel"
+                onClick={() => invoke("cancel_job", { id: activeJob.id }).catch(err => console.error("Failed to cancel job:", err))}
+              >
+                Cancel
+              </button>
+            )}
           </div>
         </div>
       )}
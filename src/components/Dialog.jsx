@@ -122,6 +122,10 @@ export function DataMigrationDialog({
               <div className="migration-option-header">{fromLabel} (Current)</div>
               {fromSummary ? (
                 <div className="migration-details">
+                  <div className="detail-row">
+                    <span className="detail-label">Games:</span>
+                    <span className="detail-value">{fromSummary.games}</span>
+                  </div>
                   <div className="detail-row">
                     <span className="detail-label">Categories:</span>
                     <span className="detail-value">{fromSummary.categories}</span>
@@ -150,6 +154,10 @@ export function DataMigrationDialog({
               <div className="migration-option-header">{toLabel} (Target)</div>
               {toSummary ? (
                 <div className="migration-details">
+                  <div className="detail-row">
+                    <span className="detail-label">Games:</span>
+                    <span className="detail-value">{toSummary.games}</span>
+                  </div>
                   <div className="detail-row">
                     <span className="detail-label">Categories:</span>
                     <span className="detail-value">{toSummary.categories}</span>
@@ -180,13 +188,13 @@ export function DataMigrationDialog({
                 checked={createBackup}
                 onChange={(e) => setCreateBackup(e.target.checked)}
               />
-              <span>Create backup of discarded data (.json.backup)</span>
+              <span>Create backup of discarded data (discarded-* folder)</span>
             </label>
           </div>
           
           <div className="migration-warning">
             ⚠️ The data you don't keep will be {createBackup ? 'backed up and then ' : ''}deleted. 
-            {createBackup && ' You can restore from the .backup file if needed.'}
+            {createBackup && ' You can restore it from the discarded-* folder in that location if needed.'}
           </div>
         </div>
         
@@ -325,3 +333,61 @@ export function TagDialog({ isOpen, currentTags, allTags, onConfirm, onCancel })
     </div>
   );
 }
+
+export function VariantDialog({ isOpen, archiveName, variants, onConfirm, onCancel }) {
+  const [selected, setSelected] = useState([]);
+
+  useEffect(() => {
+    if (isOpen) {
+      setSelected(variants);
+    }
+  }, [isOpen, variants]);
+
+  if (!isOpen) return null;
+
+  function toggleVariant(variant) {
+    setSelected(prev => prev.includes(variant)
+      ? prev.filter(v => v !== variant)
+      : [...prev, variant]);
+  }
+
+  return (
+    <div className="modal-overlay" onClick={onCancel}>
+      <div className="modal-dialog" onClick={(e) => e.stopPropagation()}>
+        <div className="modal-header">
+          <h3>Import {archiveName}</h3>
+          <button className="modal-close" onClick={onCancel}>×</button>
+        </div>
+        
+        <div className="modal-body">
+          <p>This archive contains several mod folders. Import the ones you pick as variants that can't be enabled together, or import every folder as a separate mod.</p>
+          <div className="variant-list">
+            {variants.map(variant => (
+              <label key={variant} className="checkbox-label">
+                <input
+                  type="checkbox"
+                  checked={selected.includes(variant)}
+                  onChange={() => toggleVariant(variant)}
+                />
+                <span>{variant}</span>
+              </label>
+            ))}
+          </div>
+        </div>
+        
+        <div className="modal-footer">
+          <button className="modal-btn-cancel" onClick={() => onConfirm(null)}>
+            Separate Mods
+          </button>
+          <button
+            className="modal-btn-confirm"
+            onClick={() => onConfirm(variants.filter(v => selected.includes(v)))}
+            disabled={selected.length === 0}
+          >
+            Import Variants
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -9,6 +9,13 @@ export default function ManagerView({
   mods,
   selectedCategory,
   selectedMod,
+  modKeys,
+  onRebindKey,
+  modVariables,
+  onSetVariable,
+  onApplyPreset,
+  onSavePreset,
+  onDeletePreset,
   selectedModIds,
   searchQuery,
   onSelectCategory,
@@ -31,7 +38,10 @@ export default function ManagerView({
   onUpdateTags,
   onUpdateName,
   onMoveModToCategory,
-  onSearchChange
+  onSearchChange,
+  history,
+  onUndo,
+  onRedo
 }) {
   const [leftWidth, setLeftWidth] = useState(300);
   const [rightWidth, setRightWidth] = useState(400);
@@ -211,6 +221,22 @@ export default function ManagerView({
               >
                 <Icon name="delete" size={18} /> Delete
               </button>
+              <button 
+                className="secondary-button" 
+                onClick={onUndo}
+                disabled={history.undo.length === 0}
+                title={history.undo[0] ? `Undo ${history.undo[0].label} (Ctrl+Z)` : "Nothing to undo"}
+              >
+                Undo
+              </button>
+              <button 
+                className="secondary-button" 
+                onClick={onRedo}
+                disabled={history.redo.length === 0}
+                title={history.redo[0] ? `Redo ${history.redo[0].label} (Ctrl+Y)` : "Nothing to redo"}
+              >
+                Redo
+              </button>
             </>
           )}
         </div>
@@ -226,6 +252,13 @@ export default function ManagerView({
         <div className="right-panel" style={{ width: rightWidth }}>
           <ModDetails
             mod={selectedMod}
+            keys={modKeys}
+            onRebindKey={onRebindKey}
+            variables={modVariables}
+            onSetVariable={onSetVariable}
+            onApplyPreset={onApplyPreset}
+            onSavePreset={onSavePreset}
+            onDeletePreset={onDeletePreset}
             allTags={mods.flatMap(m => m.tags || []).filter((v, i, a) => a.indexOf(v) === i)}
             onUpdateNotes={onUpdateNotes}
             onUploadPreview={onUploadPreview}
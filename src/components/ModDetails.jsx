@@ -1,12 +1,13 @@
 import { useRef, useState } from "react";
 import Icon from "./IconSimple";
 
-export default function ModDetails({ mod, allTags, onUpdateNotes, onUploadPreview, onUpdateTags, onUpdateName }) {
+export default function ModDetails({ mod, keys, onRebindKey, variables, onSetVariable, onApplyPreset, onSavePreset, onDeletePreset, allTags, onUpdateNotes, onUploadPreview, onUpdateTags, onUpdateName }) {
   const fileInputRef = useRef(null);
   const [tagInput, setTagInput] = useState("");
   const [showSuggestions, setShowSuggestions] = useState(false);
   const [isEditingName, setIsEditingName] = useState(false);
   const [editedName, setEditedName] = useState("");
+  const [selectedPreset, setSelectedPreset] = useState("");
 
   if (!mod) {
     return (
@@ -18,6 +19,8 @@ export default function ModDetails({ mod, allTags, onUpdateNotes, onUploadPrevie
   }
 
   const modTags = mod.tags || [];
+  const presets = mod.presets || [];
+  const preset = presets.some(p => p.name === selectedPreset) ? selectedPreset : presets[0]?.name || "";
   const suggestions = allTags.filter(tag => 
     tag.toLowerCase().includes(tagInput.toLowerCase()) &&
     !modTags.includes(tag) &&
@@ -199,6 +202,84 @@ export default function ModDetails({ mod, allTags, onUpdateNotes, onUploadPrevie
         </div>
       </div>
 
+      {keys.length > 0 && (
+        <div className="keys-section">
+          <div>Keys</div>
+          <div className="key-list">
+            {keys.map((binding, i) => {
+              // Only the first key of a section can be changed from here
+              const editable = keys.findIndex(k => k.file === binding.file && k.section === binding.section) === i;
+              return (
+                <div key={`${binding.file}:${binding.line}`} className="key-row" title={`${binding.file}, line ${binding.line}`}>
+                  {editable ? (
+                    <button className="key-chip key-chip-editable" onClick={() => onRebindKey(binding)} title="Change key">
+                      {binding.combo.label}
+                    </button>
+                  ) : (
+                    <span className="key-chip">{binding.combo.label}</span>
+                  )}
+                  <span className="key-description">
+                    {binding.binding_type ? `${binding.binding_type} ` : ""}{binding.description}
+                  </span>
+                </div>
+              );
+            })}
+          </div>
+        </div>
+      )}
+
+      {variables.length > 0 && (
+        <div className="keys-section">
+          <div>Variables</div>
+          <div className="key-list">
+            {variables.map(variable => (
+              <div key={`${variable.file}:${variable.name}`} className="key-row" title={variable.file}>
+                <span className="key-description">{variable.name}</span>
+                <input
+                  type="text"
+                  className="variable-input"
+                  key={variable.value ?? variable.default}
+                  defaultValue={variable.value ?? variable.default}
+                  onBlur={(e) => {
+                    const value = e.target.value.trim();
+                    if (value && value !== (variable.value ?? variable.default)) {
+                      onSetVariable(variable, value);
+                    }
+                  }}
+                  onKeyDown={(e) => e.key === "Enter" && e.target.blur()}
+                />
+              </div>
+            ))}
+          </div>
+          
+          <div className="preset-row">
+            <select
+              className="tag-input-inline"
+              value={preset}
+              onChange={(e) => setSelectedPreset(e.target.value)}
+              disabled={presets.length === 0}
+            >
+              {presets.length === 0 && <option value="">No presets</option>}
+              {presets.map(p => (
+                <option key={p.name} value={p.name}>{p.name}</option>
+              ))}
+            </select>
+            <button className="secondary-button" onClick={() => onApplyPreset(preset)} disabled={!preset}>
+              Apply
+            </button>
+            <button className="secondary-button" onClick={onSavePreset}>
+              Save
+            </button>
+            <button className="secondary-button" onClick={() => onDeletePreset(preset)} disabled={!preset}>
+              <Icon name="delete" size={16} />
+            </button>
+          </div>
+          <div className="empty-tags-hint">
+            Saved in d3dx_user.ini. Reload 3DMigoto (F10) for changes made while the game runs.
+          </div>
+        </div>
+      )}
+
       <div className="notes-section">
         <div>Notes</div>
         <textarea
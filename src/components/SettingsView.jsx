@@ -29,8 +29,11 @@ const THEME_PRESETS = [
 
 export default function SettingsView({ 
   rootFolder, 
+  watchError,
   modStrategy,
+  strategies,
   disabledFolder,
+  libraryFolder,
   dataLocation,
   appdataPath,
   localPath,
@@ -39,11 +42,27 @@ export default function SettingsView({
   onChangeRootWithMigration,
   onChangeStrategy,
   onChangeDisabledFolder,
+  onChangeLibraryFolder,
   onChangeDataLocation,
+  onScanModFolder,
+  onDetectConflicts,
+  backups,
+  onCompareBackup,
+  onRestoreBackup,
+  trash,
+  onRestoreFromTrash,
+  onPurgeTrash,
+  profiles,
+  onApplyProfile,
+  onSaveProfile,
+  onDeleteProfile,
   onExportConfig,
   onImportConfig
 }) {
   const [newRootFolder, setNewRootFolder] = React.useState(rootFolder);
+  const [selectedBackup, setSelectedBackup] = React.useState("");
+  const [selectedTrash, setSelectedTrash] = React.useState("");
+  const [selectedProfile, setSelectedProfile] = React.useState("");
   const [selectedTheme, setSelectedTheme] = React.useState(() => {
     return localStorage.getItem('theme-preset') || 'default';
   });
@@ -287,6 +306,11 @@ export default function SettingsView({
             <div className="setting-hint">
               The folder where all your mod files are stored. Changes will prompt to migrate mods.
             </div>
+            {hasGameSelected && watchError && (
+              <div className="setting-warning">
+                Changes made outside the manager won't be picked up: {watchError}
+              </div>
+            )}
           </div>
 
           <div className="setting-group">
@@ -298,8 +322,9 @@ export default function SettingsView({
                 onChange={e => onChangeStrategy(e.target.value)}
                 disabled={!hasGameSelected}
               >
-                <option value="generic_rename">Generic (Move to folder)</option>
-                <option value="wuthering_waves">Wuthering Waves (.ini rename)</option>
+                {strategies.map(strategy => (
+                  <option key={strategy.id} value={strategy.id}>{strategy.name}</option>
+                ))}
               </select>
               <button 
                 className="secondary-button strategy-add-btn"
@@ -310,10 +335,12 @@ export default function SettingsView({
               </button>
             </div>
             <div className="setting-description">
-              <strong>Generic:</strong> Moves mod folder to/from a disabled folder when toggling
-              <br />
-              <strong>Wuthering Waves:</strong> Adds/removes .bak extension to .ini files when toggling
-              <br />
+              {strategies.map(strategy => (
+                <React.Fragment key={strategy.id}>
+                  <strong>{strategy.name}:</strong> {strategy.description}
+                  <br />
+                </React.Fragment>
+              ))}
               <br />
               <strong>Custom Strategies:</strong> You can create your own mod management strategies for specific games. Click the + button to add a custom strategy.
             </div>
@@ -336,6 +363,172 @@ export default function SettingsView({
             </div>
           )}
 
+          {modStrategy === "link_deploy" && (
+            <div className="setting-group">
+              <label>Mod Library Folder Path</label>
+              <input
+                type="text"
+                className="text-input"
+                value={libraryFolder}
+                onChange={e => onChangeLibraryFolder(e.target.value)}
+                placeholder="C:\Games\ModLibrary"
+                disabled={!hasGameSelected}
+              />
+              <div className="setting-hint">
+                Folder outside the mod root where mod files are kept. Enabled mods are linked into the mod root folder.
+              </div>
+            </div>
+          )}
+
+          <div className="setting-group">
+            <label>Mod Folder Sync</label>
+            <div className="setting-buttons">
+              <button 
+                className="secondary-button" 
+                onClick={onScanModFolder}
+                disabled={!hasGameSelected}
+              >
+                <Icon name="find" size={18} /> Scan Mod Folder
+              </button>
+              <button 
+                className="secondary-button" 
+                onClick={onDetectConflicts}
+                disabled={!hasGameSelected}
+              >
+                <Icon name="search" size={18} /> Check Conflicts
+              </button>
+            </div>
+            <div className="setting-hint">
+              Compare the database with the mod folder on disk and fix folders that were added, removed or toggled outside the manager. Check Conflicts lists enabled mods that override the same texture or shader hash.
+            </div>
+          </div>
+
+          <div className="setting-group">
+            <label>Loadout Profiles</label>
+            <select
+              className="text-input"
+              value={selectedProfile || profiles[0]?.name || ""}
+              onChange={e => setSelectedProfile(e.target.value)}
+              disabled={!hasGameSelected || profiles.length === 0}
+            >
+              {profiles.length === 0 && <option value="">No profiles yet</option>}
+              {profiles.map(profile => (
+                <option key={profile.name} value={profile.name}>
+                  {profile.name} ({profile.mod_ids.length} mods)
+                </option>
+              ))}
+            </select>
+            <div className="setting-buttons">
+              <button 
+                className="secondary-button" 
+                onClick={() => onApplyProfile(selectedProfile || profiles[0].name)}
+                disabled={!hasGameSelected || profiles.length === 0}
+              >
+                <Icon name="enable" size={18} /> Apply
+              </button>
+              <button 
+                className="secondary-button" 
+                onClick={onSaveProfile}
+                disabled={!hasGameSelected}
+              >
+                <Icon name="add" size={18} /> Save Current
+              </button>
+              <button 
+                className="secondary-button" 
+                onClick={() => {
+                  onDeleteProfile(selectedProfile || profiles[0].name);
+                  setSelectedProfile("");
+                }}
+                disabled={!hasGameSelected || profiles.length === 0}
+              >
+                <Icon name="delete" size={18} /> Delete
+              </button>
+            </div>
+            <div className="setting-hint">
+              Save the mods that are enabled now as a named profile. Applying a profile enables exactly its mods and disables the rest.
+            </div>
+          </div>
+
+          <div className="setting-group">
+            <label>Database Backups</label>
+            <select
+              className="text-input"
+              value={selectedBackup || backups[0]?.file_name || ""}
+              onChange={e => setSelectedBackup(e.target.value)}
+              disabled={!hasGameSelected || backups.length === 0}
+            >
+              {backups.length === 0 && <option value="">No backups yet</option>}
+              {backups.map(backup => (
+                <option key={backup.file_name} value={backup.file_name}>
+                  {new Date(backup.created_at).toLocaleString()} ({backup.mods} mods)
+                </option>
+              ))}
+            </select>
+            <div className="setting-buttons">
+              <button 
+                className="secondary-button" 
+                onClick={() => onCompareBackup(selectedBackup || backups[0].file_name)}
+                disabled={!hasGameSelected || backups.length === 0}
+              >
+                <Icon name="find" size={18} /> Compare
+              </button>
+              <button 
+                className="secondary-button" 
+                onClick={() => onRestoreBackup(selectedBackup || backups[0].file_name)}
+                disabled={!hasGameSelected || backups.length === 0}
+              >
+                <Icon name="import" size={18} /> Restore
+              </button>
+            </div>
+            <div className="setting-hint">
+              Snapshots of this game's database are taken automatically as you make changes. The 20 most recent are kept.
+            </div>
+          </div>
+
+          <div className="setting-group">
+            <label>Recycle Bin</label>
+            <select
+              className="text-input"
+              value={selectedTrash || trash[0]?.id || ""}
+              onChange={e => setSelectedTrash(e.target.value)}
+              disabled={!hasGameSelected || trash.length === 0}
+            >
+              {trash.length === 0 && <option value="">Recycle bin is empty</option>}
+              {trash.map(entry => (
+                <option key={entry.id} value={entry.id}>
+                  {entry.name} (deleted {new Date(entry.deleted_at).toLocaleString()})
+                </option>
+              ))}
+            </select>
+            <div className="setting-buttons">
+              <button 
+                className="secondary-button" 
+                onClick={() => {
+                  onRestoreFromTrash(selectedTrash || trash[0].id);
+                  setSelectedTrash("");
+                }}
+                disabled={!hasGameSelected || trash.length === 0}
+              >
+                <Icon name="import" size={18} /> Restore
+              </button>
+              <button 
+                className="secondary-button" 
+                onClick={() => onPurgeTrash(30)}
+              >
+                <Icon name="delete" size={18} /> Purge Older Than 30 Days
+              </button>
+              <button 
+                className="secondary-button" 
+                onClick={() => onPurgeTrash(null)}
+              >
+                <Icon name="delete" size={18} /> Empty
+              </button>
+            </div>
+            <div className="setting-hint">
+              Deleted mods are moved here with their notes and tags. Restoring puts the folder back where it was.
+            </div>
+          </div>
+
           <div className="setting-group">
             <label>Configuration Import/Export</label>
             <div className="setting-buttons">
@@ -49,7 +49,8 @@ export default function TagsView({
       name: tagName,
       description: '',
       preview: null,
-      mutually_exclusive: false
+      mutually_exclusive: false,
+      exclusive_mode: 'refuse'
     };
   }
 
@@ -178,6 +179,7 @@ function TagDetail({
   const [editedName, setEditedName] = useState(tag);
   const [description, setDescription] = useState(metadata.description || '');
   const [mutuallyExclusive, setMutuallyExclusive] = useState(metadata.mutually_exclusive || false);
+  const [exclusiveMode, setExclusiveMode] = useState(metadata.exclusive_mode || 'refuse');
 
   function handleNameEdit() {
     if (editedName.trim() && editedName !== tag) {
@@ -196,6 +198,11 @@ function TagDetail({
     onUpdate(tag, { ...metadata, mutually_exclusive: value });
   }
 
+  function handleExclusiveModeChange(value) {
+    setExclusiveMode(value);
+    onUpdate(tag, { ...metadata, exclusive_mode: value });
+  }
+
   function handleImageUpload(e) {
     const file = e.target.files[0];
     if (!file) return;
@@ -333,6 +340,16 @@ function TagDetail({
             ⓘ
           </span>
         </label>
+        {mutuallyExclusive && (
+          <select
+            className="text-input"
+            value={exclusiveMode}
+            onChange={(e) => handleExclusiveModeChange(e.target.value)}
+          >
+            <option value="refuse">Refuse to enable while another is enabled</option>
+            <option value="auto_disable">Disable the others automatically</option>
+          </select>
+        )}
         {mutuallyExclusive && status.status === 'conflict' && (
           <div className="conflict-warning">
             ⚠️ Multiple mods are enabled with this tag!